@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::basic_types::ray::Ray;
+use lib::basic_types::ray::RayTraits;
+
+use lib::gfx::hittable::hittables::Hittable;
+use lib::gfx::hittable::hittables::hittable_list::HittableList;
+use lib::gfx::hittable::hittables::hittable_list::HittableListTrait;
+use lib::gfx::hittable::hittables::sphere::Sphere;
+use lib::gfx::hittable::hittables::sphere::SphereData;
+
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+
+// Rays fired per iteration so the per-ray cost dominates the loop overhead
+const RAYS: usize = 1024;
+
+// Spheres scattered in a box in front of the origin
+fn random_world(n: usize, rng: &mut SmallRng) -> HittableList {
+    let mut world = HittableList::new();
+    let material = Rc::new(Lambertian::new(Color::new((0.5, 0.5, 0.5))));
+    for _ in 0..n {
+        let center = Point3::new((rng.gen_range(-10.0, 10.0), rng.gen_range(-10.0, 10.0), rng.gen_range(-30.0, -5.0)));
+        world.add(Rc::new(SphereData::new(center, rng.gen_range(0.2, 1.0), material.clone())));
+    }
+    world
+}
+
+fn random_rays(rng: &mut SmallRng) -> Vec<Ray> {
+    (0..RAYS).map(|_| {
+        let dir = Vec3::new((rng.gen_range(-0.5, 0.5), rng.gen_range(-0.5, 0.5), -1.0));
+        Ray::new(Point3::new((0.0, 0.0, 0.0)), dir)
+    }).collect()
+}
+
+fn bench_hit(c: &mut Criterion) {
+    let mut rng = SmallRng::from_seed([7; 16]);
+    let rays = random_rays(&mut rng);
+
+    let mut group = c.benchmark_group("hit");
+    group.throughput(Throughput::Elements(RAYS as u64));
+
+    let sphere = SphereData::new(Point3::new((0.0, 0.0, -10.0)), 3.0,
+        Rc::new(Lambertian::new(Color::new((0.5, 0.5, 0.5)))));
+    group.bench_function("sphere", |b| b.iter(|| {
+        for r in &rays {
+            black_box(sphere.hit(*r, 0.001, f64::INFINITY));
+        }
+    }));
+
+    for n in [1, 10, 100, 500].iter() {
+        let world = random_world(*n, &mut rng);
+        group.bench_with_input(BenchmarkId::new("hittable_list", n), &world, |b, world| b.iter(|| {
+            for r in &rays {
+                black_box(world.hit(*r, 0.001, f64::INFINITY));
+            }
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hit);
+criterion_main!(benches);
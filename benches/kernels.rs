@@ -0,0 +1,194 @@
+use std::rc::Rc;
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::basic_types::ray::Ray;
+use lib::basic_types::ray::RayTraits;
+
+use lib::gfx::camera::Camera;
+use lib::gfx::camera::CameraTraits;
+
+use lib::gfx::hittable::HitRecord;
+use lib::gfx::hittable::hittables::Hittable;
+use lib::gfx::hittable::hittables::sphere::Sphere;
+use lib::gfx::hittable::hittables::sphere::SphereData;
+
+use lib::gfx::material::Material;
+use lib::gfx::material::coated::CoatedMaterial;
+use lib::gfx::material::coated::CoatedMaterialFn;
+use lib::gfx::material::conductor::Conductor;
+use lib::gfx::material::conductor::ConductorFn;
+use lib::gfx::material::dielectric::Dielectric;
+use lib::gfx::material::dielectric::DielectricFn;
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+use lib::gfx::material::metal::Metal;
+use lib::gfx::material::metal::MetalFn;
+use lib::gfx::material::mix::MixMaterial;
+use lib::gfx::material::mix::MixMaterialFn;
+use lib::gfx::material::normal_mapped::NormalMapped;
+use lib::gfx::material::normal_mapped::NormalMappedFn;
+use lib::gfx::material::oren_nayar::OrenNayar;
+use lib::gfx::material::oren_nayar::OrenNayarFn;
+use lib::gfx::material::principled::Principled;
+use lib::gfx::material::principled::PrincipledFn;
+use lib::gfx::material::rough_dielectric::RoughDielectric;
+use lib::gfx::material::rough_dielectric::RoughDielectricFn;
+use lib::gfx::material::sheen::Sheen;
+use lib::gfx::material::sheen::SheenFn;
+use lib::gfx::material::subsurface::Subsurface;
+use lib::gfx::material::subsurface::SubsurfaceFn;
+use lib::gfx::material::thin_dielectric::ThinDielectric;
+use lib::gfx::material::thin_dielectric::ThinDielectricFn;
+use lib::gfx::material::thin_film::ThinFilm;
+use lib::gfx::material::thin_film::ThinFilmFn;
+
+use lib::gfx::texture::Texture;
+use lib::gfx::texture::checker::CheckerTexture;
+use lib::gfx::texture::checker::CheckerTextureFn;
+use lib::gfx::texture::solid_color::SolidColor;
+use lib::gfx::texture::solid_color::SolidColorFn;
+
+// Samples processed per iteration so the per-sample cost dominates the loop overhead
+const SAMPLES: usize = 1024;
+
+fn random_vectors(rng: &mut SmallRng) -> Vec<Vec3> {
+    (0..SAMPLES).map(|_| Vec3::new((rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0))))
+        .collect()
+}
+
+fn bench_vec3(c: &mut Criterion) {
+    let mut rng = SmallRng::from_seed([3; 16]);
+    let a = random_vectors(&mut rng);
+    let b = random_vectors(&mut rng);
+    let n = Vec3::new((0.0, 1.0, 0.0));
+
+    let mut group = c.benchmark_group("vec3");
+    group.throughput(Throughput::Elements(SAMPLES as u64));
+    group.bench_function("add_mul", |bench| bench.iter(|| {
+        for (x, y) in a.iter().zip(b.iter()) {
+            black_box(*x + 0.5 * *y);
+        }
+    }));
+    group.bench_function("dot", |bench| bench.iter(|| {
+        for (x, y) in a.iter().zip(b.iter()) {
+            black_box(x.dot(*y));
+        }
+    }));
+    group.bench_function("cross", |bench| bench.iter(|| {
+        for (x, y) in a.iter().zip(b.iter()) {
+            black_box(x.cross(*y));
+        }
+    }));
+    group.bench_function("unitize", |bench| bench.iter(|| {
+        for x in &a {
+            black_box(x.unitize());
+        }
+    }));
+    group.bench_function("reflect", |bench| bench.iter(|| {
+        for x in &a {
+            black_box(x.reflect(n));
+        }
+    }));
+    group.bench_function("refract", |bench| bench.iter(|| {
+        for x in &a {
+            black_box(x.unitize().refract(n, 1.0 / 1.5));
+        }
+    }));
+    group.bench_function("random_unit_vector", |bench| bench.iter(|| {
+        for _ in 0..SAMPLES {
+            black_box(Vec3::random_unit_vector());
+        }
+    }));
+    group.finish();
+}
+
+fn bench_camera(c: &mut Criterion) {
+    let cam = Camera::new(Point3::new((-2.0, 2.0, 1.0)), Point3::new((0.0, 0.0, -1.0)),
+        Vec3::new((0.0, 1.0, 0.0)), 90.0, 16.0 / 9.0);
+    let mut rng = SmallRng::from_seed([5; 16]);
+    let uv: Vec<(f64, f64)> = (0..SAMPLES).map(|_| (rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0))).collect();
+
+    let mut group = c.benchmark_group("camera");
+    group.throughput(Throughput::Elements(SAMPLES as u64));
+    group.bench_function("get_ray", |b| b.iter(|| {
+        for (u, v) in &uv {
+            black_box(cam.get_ray(*u, *v));
+        }
+    }));
+    group.finish();
+}
+
+fn solid(c: (f64, f64, f64)) -> Rc<dyn Texture> {
+    Rc::new(SolidColor::new(Color::new(c)))
+}
+
+// Every material the crate ships, with typical parameters
+fn materials() -> Vec<(&'static str, Rc<dyn Material>)> {
+    let red = Color::new((0.7, 0.2, 0.2));
+    let lambertian: Rc<dyn Material> = Rc::new(Lambertian::new(red));
+    let gold: Rc<dyn Material> = Rc::new(Conductor::gold(0.3));
+    let checker: Rc<dyn Texture> = Rc::new(CheckerTexture::new(solid((0.0, 0.0, 0.0)), solid((1.0, 1.0, 1.0)), 10.0));
+    vec![
+        ("lambertian", lambertian.clone()),
+        ("metal", Rc::new(Metal::new(red, 0.3))),
+        ("dielectric", Rc::new(Dielectric::new(1.5))),
+        ("conductor", gold.clone()),
+        ("rough_dielectric", Rc::new(RoughDielectric::new(1.5, 0.3))),
+        ("thin_dielectric", Rc::new(ThinDielectric::new(1.5))),
+        ("principled", Rc::new(Principled::new(solid((0.7, 0.2, 0.2))))),
+        ("mix", Rc::new(MixMaterial::new(lambertian.clone(), gold.clone(), 0.5))),
+        ("coated", Rc::new(CoatedMaterial::new(lambertian.clone(), 1.5))),
+        ("oren_nayar", Rc::new(OrenNayar::new(red, 20.0))),
+        ("sheen", Rc::new(Sheen::new(red, Color::new((1.0, 1.0, 1.0)), 0.5))),
+        ("normal_mapped", Rc::new(NormalMapped::new_bump_map(lambertian, checker, 0.01))),
+        ("thin_film", Rc::new(ThinFilm::new_conductor(Color::new((2.7, 2.5, 2.2)), Color::new((3.8, 3.4, 3.0)),
+            0.2, 2.4, Rc::new(SolidColor::from_scalar(120.0))))),
+        ("subsurface", Rc::new(Subsurface::new(Color::new((0.9, 0.8, 0.7)), Color::new((0.3, 0.1, 0.05)), 0.0)))
+    ]
+}
+
+// Rays hitting a unit sphere from random directions around the viewer, with their records
+fn hits(material: Rc<dyn Material>, rng: &mut SmallRng) -> Vec<(Ray, HitRecord)> {
+    let sphere = SphereData::new(Point3::new((0.0, 0.0, -3.0)), 1.0, material);
+    let mut hits = Vec::with_capacity(SAMPLES);
+    while hits.len() < SAMPLES {
+        let dir = Vec3::new((rng.gen_range(-0.3, 0.3), rng.gen_range(-0.3, 0.3), -1.0));
+        let r = Ray::new(Point3::new((0.0, 0.0, 0.0)), dir);
+        if let Some(rec) = sphere.hit(r, 0.001, f64::INFINITY) {
+            hits.push((r, rec));
+        }
+    }
+    hits
+}
+
+fn bench_scatter(c: &mut Criterion) {
+    let mut rng = SmallRng::from_seed([9; 16]);
+    let mut group = c.benchmark_group("scatter");
+    group.throughput(Throughput::Elements(SAMPLES as u64));
+    for (name, material) in materials() {
+        let hits = hits(material.clone(), &mut rng);
+        group.bench_function(name, |b| b.iter(|| {
+            for (r, rec) in &hits {
+                black_box(material.scatter(r, rec));
+            }
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_vec3, bench_camera, bench_scatter);
+criterion_main!(benches);
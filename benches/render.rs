@@ -0,0 +1,46 @@
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+
+use lib::gfx::scene;
+use lib::gfx::scene::Scene;
+
+use lib::gfx::screen::Screen;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+const IMAGE_WIDTH: i32 = 64;
+const IMAGE_HEIGHT: i32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as i32;
+const SAMPLES_PER_PIXEL: i32 = 4;
+const MAX_DEPTH: i32 = 50;
+
+fn render(scene: &Scene) -> Screen {
+    let settings = RenderSettings {
+        width: IMAGE_WIDTH,
+        height: IMAGE_HEIGHT,
+        samples_per_pixel: SAMPLES_PER_PIXEL,
+        max_depth: MAX_DEPTH,
+        ..RenderSettings::default()
+    };
+    Renderer::new(scene, settings).render().screen
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    // counts camera rays, each of which may bounce up to MAX_DEPTH times
+    group.throughput(Throughput::Elements((IMAGE_WIDTH * IMAGE_HEIGHT * SAMPLES_PER_PIXEL) as u64));
+    group.sample_size(10);
+    for name in scene::PRESETS.iter() {
+        let scene = scene::by_name(name, ASPECT_RATIO).unwrap();
+        group.bench_function(*name, |b| b.iter(|| black_box(render(&scene))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
@@ -1,2 +1,5 @@
-pub mod vec3;
-pub mod ray;
\ No newline at end of file
+pub mod vec3;
+pub mod ray;
+pub mod onb;
+pub mod spectrum;
+pub mod random;
\ No newline at end of file
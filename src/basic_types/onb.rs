@@ -0,0 +1,53 @@
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+// Orthonormal basis, w is the surface normal for shading frames
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3
+}
+
+pub trait OnbTraits {
+    fn build_from_w(n: Vec3) -> Self;
+
+    fn u(&self) -> Vec3;
+    fn v(&self) -> Vec3;
+    fn w(&self) -> Vec3;
+
+    // local (u, v, w) coordinates to world space
+    fn local(&self, a: Vec3) -> Vec3;
+    // world space to local (u, v, w) coordinates
+    fn to_local(&self, a: Vec3) -> Vec3;
+}
+
+impl OnbTraits for Onb {
+    fn build_from_w(n: Vec3) -> Self {
+        let w = n.unitize();
+        let a = if w.x().abs() > 0.9 {Vec3::new((0.0, 1.0, 0.0))} else {Vec3::new((1.0, 0.0, 0.0))};
+        let v = w.cross(a).unitize();
+        let u = v.cross(w);
+        Onb { u, v, w }
+    }
+
+    fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    fn v(&self) -> Vec3 {
+        self.v
+    }
+
+    fn w(&self) -> Vec3 {
+        self.w
+    }
+
+    fn local(&self, a: Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    fn to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::new((a.dot(self.u), a.dot(self.v), a.dot(self.w)))
+    }
+}
@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+
+use rand::FromEntropy;
+use rand::RngCore;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+thread_local! {
+    static GENERATOR: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+// Handle to this thread's generator, used like rand::thread_rng() but
+// reproducible once seeded
+#[derive(Debug, Copy, Clone)]
+pub struct TracerRng;
+
+pub fn rng() -> TracerRng {
+    TracerRng
+}
+
+// splitmix64 step, spreads nearby seeds over the whole state space
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Standalone generator for work that must not disturb the per-pixel streams,
+// such as laying out a scene
+pub fn seeded(seed: u64) -> SmallRng {
+    let a = mix(seed);
+    let b = mix(a);
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&a.to_le_bytes());
+    bytes[8..].copy_from_slice(&b.to_le_bytes());
+    SmallRng::from_seed(bytes)
+}
+
+// Restarts this thread's generator so every draw after it is reproducible
+pub fn seed(seed: u64) {
+    GENERATOR.with(|generator| *generator.borrow_mut() = seeded(seed));
+}
+
+// Runs f with generator standing in for this thread's generator, so a
+// stream can be paused and picked up again later where it left off
+pub fn with_generator<T, F: FnOnce() -> T>(generator: &mut SmallRng, f: F) -> T {
+    GENERATOR.with(|current| std::mem::swap(&mut *current.borrow_mut(), generator));
+    let result = f();
+    GENERATOR.with(|current| std::mem::swap(&mut *current.borrow_mut(), generator));
+    result
+}
+
+// Seed for one pixel of a render, so each pixel's samples do not depend on
+// the order pixels are visited in
+pub fn pixel_seed(seed: u64, x: i32, y: i32) -> u64 {
+    mix(seed ^ mix(((x as u32 as u64) << 32) | y as u32 as u64))
+}
+
+impl RngCore for TracerRng {
+    fn next_u32(&mut self) -> u32 {
+        GENERATOR.with(|generator| generator.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        GENERATOR.with(|generator| generator.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        GENERATOR.with(|generator| generator.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        GENERATOR.with(|generator| generator.borrow_mut().try_fill_bytes(dest))
+    }
+}
@@ -1,40 +1,63 @@
-use crate::basic_types::vec3::Vec3;
-use crate::basic_types::vec3::Point3;
-
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Ray {
-    orig: Point3,
-    dir: Vec3
-}
-
-pub trait RayTraits {
-    fn new(orig: Point3, 
-            dir: Vec3) -> Self;
-    
-    fn origin(&self) -> Point3;
-    fn direction(&self) -> Vec3;
-
-    fn at(&self, t: f64) -> Point3;
-}
-
-impl RayTraits for Ray {
-    fn new(orig: Point3, dir: Vec3) -> Ray {
-        Ray {
-            orig: orig,
-            dir: dir
-        }
-    }
-    
-    fn origin(&self) -> Point3 {
-        self.orig
-    }
-
-    fn direction(&self) -> Vec3 {
-        self.dir
-    }
-    
-    fn at(&self, t: f64) -> Point3 {
-        self.orig + t * self.dir
-    }
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Point3;
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    orig: Point3,
+    dir: Vec3,
+    // nanometers, only set when tracing in spectral mode
+    wavelength: Option<f64>
+}
+
+pub trait RayTraits {
+    fn new(orig: Point3, 
+            dir: Vec3) -> Self;
+    fn new_spectral(orig: Point3, dir: Vec3, wavelength: f64) -> Self;
+    
+    fn origin(&self) -> Point3;
+    fn direction(&self) -> Vec3;
+    fn wavelength(&self) -> Option<f64>;
+    // Same ray carrying the given wavelength, so scattered rays keep their path's
+    fn with_wavelength(&self, wavelength: Option<f64>) -> Self;
+
+    fn at(&self, t: f64) -> Point3;
+}
+
+impl RayTraits for Ray {
+    fn new(orig: Point3, dir: Vec3) -> Ray {
+        Ray {
+            orig,
+            dir,
+            wavelength: None
+        }
+    }
+
+    fn new_spectral(orig: Point3, dir: Vec3, wavelength: f64) -> Ray {
+        Ray {
+            orig,
+            dir,
+            wavelength: Some(wavelength)
+        }
+    }
+    
+    fn origin(&self) -> Point3 {
+        self.orig
+    }
+
+    fn direction(&self) -> Vec3 {
+        self.dir
+    }
+
+    fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
+    fn with_wavelength(&self, wavelength: Option<f64>) -> Ray {
+        Ray { wavelength, ..*self }
+    }
+    
+    fn at(&self, t: f64) -> Point3 {
+        self.orig + t * self.dir
+    }
 }
\ No newline at end of file
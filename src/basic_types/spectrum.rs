@@ -0,0 +1,94 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+// Range of wavelengths in nanometers that spectral paths are traced at
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+// Wavelength where the d-line IOR of glasses is quoted, used by the RGB path
+pub const LAMBDA_D: f64 = 587.6;
+
+// Piecewise gaussian used by the analytic color matching function fit
+fn lobe(lambda: f64, mu: f64, sigma_lo: f64, sigma_hi: f64) -> f64 {
+    let t = (lambda - mu) / if lambda < mu {sigma_lo} else {sigma_hi};
+    (-0.5 * t * t).exp()
+}
+
+// CIE 1931 2 degree color matching functions, Wyman, Sloan and Shirley 2013
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    let x = 1.056 * lobe(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * lobe(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * lobe(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * lobe(lambda, 459.0, 26.0, 13.8);
+    Vec3::new((x, y, z))
+}
+
+// CIE XYZ to linear sRGB primaries
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+    Color::new((3.2404542 * xyz.x() - 1.5371385 * xyz.y() - 0.4985314 * xyz.z(),
+        -0.9692660 * xyz.x() + 1.8760108 * xyz.y() + 0.0415560 * xyz.z(),
+        0.0556434 * xyz.x() - 0.2040259 * xyz.y() + 1.0572252 * xyz.z()))
+}
+
+// Importance samples the visible range roughly following the luminance response
+pub fn sample_wavelength(u: f64) -> f64 {
+    538.0 - 138.888889 * (0.85691062 - 1.82750197 * u).atanh()
+}
+
+pub fn wavelength_pdf(lambda: f64) -> f64 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    let c = (0.0072 * (lambda - 538.0)).cosh();
+    0.003939804229326285 / (c * c)
+}
+
+fn smoothstep(lambda: f64, lo: f64, hi: f64) -> f64 {
+    let t = ((lambda - lo) / (hi - lo)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Smooth red, green and blue basis spectra that sum to one at every wavelength,
+// so white and greys upsample to flat spectra and reflectances stay within [0, 1]
+fn rgb_basis(lambda: f64) -> Vec3 {
+    let r = smoothstep(lambda, 570.0, 610.0);
+    let b = 1.0 - smoothstep(lambda, 470.0, 510.0);
+    Vec3::new((r, 1.0 - r - b, b))
+}
+
+// Value at lambda of a smooth spectrum whose color is close to c
+pub fn rgb_to_spectrum(c: Color, lambda: f64) -> f64 {
+    c.dot(rgb_basis(lambda))
+}
+
+// Single wavelength estimate of the linear RGB color of a spectrum that has
+// value at lambda, where lambda was drawn with sample_wavelength
+pub fn spectrum_to_rgb(value: f64, lambda: f64) -> Color {
+    let pdf = wavelength_pdf(lambda);
+    if pdf == 0.0 {
+        return Color::new((0.0, 0.0, 0.0));
+    }
+    let rgb = xyz_to_rgb(cie_xyz(lambda)) * (value / pdf);
+    // white balance so a flat spectrum comes back as white
+    Color::new((rgb.x() / WHITE.0, rgb.y() / WHITE.1, rgb.z() / WHITE.2))
+}
+
+// Linear RGB of the flat unit spectrum through the fitted matching functions
+const WHITE: (f64, f64, f64) = (128.335, 101.544, 97.117);
+
+// Linear RGB of a reflectance spectrum, integrated with the midpoint rule
+// over the given number of wavelengths
+pub fn reflectance_to_rgb<F: Fn(f64) -> f64>(reflectance: F, samples: i32) -> Color {
+    let step = (LAMBDA_MAX - LAMBDA_MIN) / samples as f64;
+    let mut xyz = Vec3::new((0.0, 0.0, 0.0));
+    for i in 0..samples {
+        let lambda = LAMBDA_MIN + (i as f64 + 0.5) * step;
+        xyz += reflectance(lambda) * step * cie_xyz(lambda);
+    }
+    let rgb = xyz_to_rgb(xyz);
+    Color::new((rgb.x() / WHITE.0, rgb.y() / WHITE.1, rgb.z() / WHITE.2))
+}
@@ -0,0 +1,345 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::ColorTraits;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::screen::Screen;
+use crate::gfx::screen::ScreenTraits;
+use crate::gfx::texture::image::read_ppm;
+
+// Keeps relMSE finite where the reference is black, as in Rousselle et al. 2011
+const REL_EPSILON: f64 = 1e-2;
+// Gaussian window of Wang et al. 2004
+const SSIM_RADIUS: i64 = 5;
+const SSIM_SIGMA: f64 = 1.5;
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+// Heatmaps are scaled so this fraction of pixels stays below full scale
+const HEATMAP_PERCENTILE: f64 = 0.99;
+
+// Linear radiance, row major with the top row first
+pub struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<Color>
+}
+
+pub trait ImageTraits {
+    fn new(width: usize, height: usize, data: Vec<Color>) -> Self;
+    // Undoes the gamma 2 encoding applied by color_pixel
+    fn from_screen(screen: &Screen) -> Self;
+    // Gamma 2 encodes it again, the inverse of from_screen
+    fn to_screen(&self) -> Screen;
+    // PPM and PNG are read as gamma 2 encoded like our renders, Radiance HDR as linear
+    fn load(path: &str) -> io::Result<Self> where Self: Sized;
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn pixel(&self, x: usize, y: usize) -> Color;
+}
+
+impl ImageTraits for Image {
+    fn new(width: usize, height: usize, data: Vec<Color>) -> Self {
+        Image { width, height, data }
+    }
+
+    fn from_screen(screen: &Screen) -> Self {
+        let (width, height) = (screen.width() as usize, screen.height() as usize);
+        let mut data = Vec::with_capacity(width * height);
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let c = screen.pixel(x, y);
+                data.push(c * c);
+            }
+        }
+        Image { width, height, data }
+    }
+
+    fn to_screen(&self) -> Screen {
+        let mut screen = Screen::empty_screen(self.width as i32, self.height as i32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                screen.color_pixel(x, self.height - 1 - y, self.pixel(x, y), 1);
+            }
+        }
+        screen
+    }
+
+    fn load(path: &str) -> io::Result<Self> {
+        let extension = Path::new(path).extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let (width, height, data) = match extension.as_str() {
+            "png" => read_png(path)?,
+            "hdr" | "pic" => return read_hdr(path),
+            _ => read_ppm(path)?
+        };
+        let data = data.iter().map(|c| *c * *c).collect();
+        Ok(Image { width, height, data })
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Color {
+        self.data[y * self.width + x]
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// Any 8 or 16 bit PNG into [0, 1] values, alpha is dropped
+fn read_png(path: &str) -> io::Result<(usize, usize, Vec<Color>)> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| invalid(&e.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| invalid(&e.to_string()))?;
+    let channels = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+
+    let mut data = Vec::with_capacity(width * height);
+    for row in buffer.chunks(info.line_size).take(height) {
+        for texel in row.chunks(channels).take(width) {
+            let v = |i: usize| texel[i] as f64 / 255.0;
+            data.push(if channels < 3 {Color::new((v(0), v(0), v(0)))} else {Color::new((v(0), v(1), v(2)))});
+        }
+    }
+    Ok((width, height, data))
+}
+
+// Radiance RGBE with flat or run length encoded scanlines
+fn read_hdr(path: &str) -> io::Result<Image> {
+    let bytes = fs::read(path)?;
+    let mut pos = 0;
+    let mut line = || -> io::Result<String> {
+        let start = pos;
+        while pos < bytes.len() && bytes[pos] != b'\n' {
+            pos += 1;
+        }
+        if pos >= bytes.len() {
+            return Err(invalid("truncated HDR header"));
+        }
+        pos += 1;
+        Ok(String::from_utf8_lossy(&bytes[start..pos - 1]).trim().to_string())
+    };
+    if !line()?.starts_with("#?") {
+        return Err(invalid("missing HDR signature"));
+    }
+    loop {
+        let header = line()?;
+        if header.is_empty() {
+            break;
+        }
+        if header.starts_with("FORMAT=") && header != "FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid("unsupported HDR format, expected 32-bit_rle_rgbe"));
+        }
+    }
+    let resolution = line()?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    if fields.len() != 4 || fields[0] != "-Y" || fields[2] != "+X" {
+        return Err(invalid("unsupported HDR orientation, expected -Y H +X W"));
+    }
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| invalid("bad HDR resolution"));
+    let (height, width) = (parse(fields[1])?, parse(fields[3])?);
+
+    let mut data = Vec::with_capacity(width * height);
+    // one plane per component, the order run length encoded scanlines are stored in
+    let mut planes = vec![vec![0u8; width]; 4];
+    for _ in 0..height {
+        let rle = (8..32768).contains(&width) && bytes.len() >= pos + 4
+            && bytes[pos] == 2 && bytes[pos + 1] == 2 && bytes[pos + 2] & 0x80 == 0;
+        if rle {
+            if ((bytes[pos + 2] as usize) << 8 | bytes[pos + 3] as usize) != width {
+                return Err(invalid("bad HDR scanline length"));
+            }
+            pos += 4;
+            for plane in planes.iter_mut() {
+                let mut x = 0;
+                while x < width {
+                    let count = *bytes.get(pos).ok_or_else(|| invalid("truncated HDR data"))? as usize;
+                    pos += 1;
+                    let (run, count) = if count > 128 {(true, count - 128)} else {(false, count)};
+                    if count == 0 || x + count > width {
+                        return Err(invalid("bad HDR run length"));
+                    }
+                    if run {
+                        let value = *bytes.get(pos).ok_or_else(|| invalid("truncated HDR data"))?;
+                        plane[x..x + count].iter_mut().for_each(|v| *v = value);
+                        pos += 1;
+                    } else {
+                        let values = bytes.get(pos..pos + count).ok_or_else(|| invalid("truncated HDR data"))?;
+                        plane[x..x + count].copy_from_slice(values);
+                        pos += count;
+                    }
+                    x += count;
+                }
+            }
+        } else {
+            let texels = bytes.get(pos..pos + 4 * width).ok_or_else(|| invalid("truncated HDR data"))?;
+            for (c, plane) in planes.iter_mut().enumerate() {
+                plane.iter_mut().zip(texels.chunks(4)).for_each(|(v, rgbe)| *v = rgbe[c]);
+            }
+            pos += 4 * width;
+        }
+        let texels = planes[0].iter().zip(&planes[1]).zip(&planes[2]).zip(&planes[3]);
+        for (((r, g), b), e) in texels {
+            let scale = if *e == 0 {0.0} else {2f64.powi(*e as i32 - 136)};
+            data.push(Color::new((*r as f64 * scale, *g as f64 * scale, *b as f64 * scale)));
+        }
+    }
+    Ok(Image { width, height, data })
+}
+
+pub struct Metrics {
+    pub mse: f64,
+    pub rmse: f64,
+    // peak signal of 1, the white point of our renders
+    pub psnr: f64,
+    // structural similarity of the displayed luminance, 1 for identical images
+    pub ssim: f64,
+    pub rel_mse: f64
+}
+
+fn assert_same_size(reference: &Image, test: &Image) {
+    assert!(reference.width == test.width && reference.height == test.height,
+        "cannot compare a {}x{} image with a {}x{} one",
+        reference.width, reference.height, test.width, test.height);
+}
+
+// Error of test against reference, both in linear radiance and the same size
+pub fn compare(reference: &Image, test: &Image) -> Metrics {
+    assert_same_size(reference, test);
+    let n = (reference.data.len() * 3).max(1) as f64;
+    let mut squared = 0.0;
+    let mut relative = 0.0;
+    for (r, t) in reference.data.iter().zip(test.data.iter()) {
+        for (r, t) in [(r.x(), t.x()), (r.y(), t.y()), (r.z(), t.z())].iter() {
+            let d = (t - r) * (t - r);
+            squared += d;
+            relative += d / (r * r + REL_EPSILON);
+        }
+    }
+    let mse = squared / n;
+    Metrics {
+        mse,
+        rmse: mse.sqrt(),
+        psnr: if mse > 0.0 {-10.0 * mse.log10()} else {f64::INFINITY},
+        ssim: ssim(reference, test),
+        rel_mse: relative / n
+    }
+}
+
+// Luminance as it appears on screen, gamma 2 encoded and clipped
+fn display_luminance(image: &Image) -> Vec<f64> {
+    image.data.iter().map(|c| c.luminance().clamp(0.0, 1.0).sqrt()).collect()
+}
+
+// Separable Gaussian blur, the window is renormalized where it leaves the image
+fn blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let taps: Vec<f64> = (-SSIM_RADIUS..=SSIM_RADIUS)
+        .map(|i| (-((i * i) as f64) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp())
+        .collect();
+    let pass = |values: &[f64], horizontal: bool| -> Vec<f64> {
+        let mut out = vec![0.0; values.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let (mut sum, mut weight) = (0.0, 0.0);
+                for (k, tap) in taps.iter().enumerate() {
+                    let offset = k as i64 - SSIM_RADIUS;
+                    let (qx, qy) = if horizontal {(x as i64 + offset, y as i64)} else {(x as i64, y as i64 + offset)};
+                    if qx < 0 || qy < 0 || qx >= width as i64 || qy >= height as i64 {
+                        continue;
+                    }
+                    sum += tap * values[qy as usize * width + qx as usize];
+                    weight += tap;
+                }
+                out[y * width + x] = sum / weight;
+            }
+        }
+        out
+    };
+    pass(&pass(values, true), false)
+}
+
+// Mean structural similarity of Wang et al. 2004 over every pixel
+pub fn ssim(reference: &Image, test: &Image) -> f64 {
+    assert_same_size(reference, test);
+    let (w, h) = (reference.width, reference.height);
+    if w == 0 || h == 0 {
+        return 1.0;
+    }
+    let x = display_luminance(reference);
+    let y = display_luminance(test);
+    let product = |a: &[f64], b: &[f64]| -> Vec<f64> {a.iter().zip(b).map(|(a, b)| a * b).collect()};
+    let mu_x = blur(&x, w, h);
+    let mu_y = blur(&y, w, h);
+    let xx = blur(&product(&x, &x), w, h);
+    let yy = blur(&product(&y, &y), w, h);
+    let xy = blur(&product(&x, &y), w, h);
+
+    let mut sum = 0.0;
+    for i in 0..w * h {
+        let (mx, my) = (mu_x[i], mu_y[i]);
+        let var_x = xx[i] - mx * mx;
+        let var_y = yy[i] - my * my;
+        let cov = xy[i] - mx * my;
+        sum += (2.0 * mx * my + SSIM_C1) * (2.0 * cov + SSIM_C2)
+            / ((mx * mx + my * my + SSIM_C1) * (var_x + var_y + SSIM_C2));
+    }
+    sum / (w * h) as f64
+}
+
+// Blue through cyan, green and yellow to red for t in [0, 1]
+fn false_color(t: f64) -> Color {
+    const STOPS: [(f64, f64, f64); 5] = [(0.0, 0.0, 0.5), (0.0, 0.8, 1.0), (0.1, 0.8, 0.1), (1.0, 0.9, 0.0), (0.9, 0.0, 0.0)];
+    let x = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (x as usize).min(STOPS.len() - 2);
+    let f = x - i as f64;
+    let (a, b) = (Color::new(STOPS[i]), Color::new(STOPS[i + 1]));
+    (1.0 - f) * a + f * b
+}
+
+// Per pixel relative squared error in false color, returned with the error
+// shown at full scale, zero for identical images. Pixels whose error isn't
+// finite, NaN from a broken sample say, are drawn at full scale and left out
+// of picking it. The result is ready for write_to_ppm.
+pub fn heatmap(reference: &Image, test: &Image) -> (Screen, f64) {
+    assert_same_size(reference, test);
+    let (w, h) = (reference.width, reference.height);
+    let errors: Vec<f64> = reference.data.iter().zip(test.data.iter())
+        .map(|(r, t)| {
+            let d = *t - *r;
+            (d.x() * d.x() / (r.x() * r.x() + REL_EPSILON)
+                + d.y() * d.y() / (r.y() * r.y() + REL_EPSILON)
+                + d.z() * d.z() / (r.z() * r.z() + REL_EPSILON)) / 3.0
+        })
+        .collect();
+
+    let mut sorted: Vec<f64> = errors.iter().cloned().filter(|e| e.is_finite()).collect();
+    sorted.sort_by(f64::total_cmp);
+    let index = ((sorted.len() as f64 * HEATMAP_PERCENTILE) as usize).min(sorted.len().saturating_sub(1));
+    let scale = sorted.get(index).cloned().unwrap_or(0.0);
+    // a few differing pixels still show up when the percentile is zero
+    let scale = if scale > 0.0 {scale} else {sorted.last().cloned().unwrap_or(0.0)};
+    // images that only differ where the error isn't finite aren't identical
+    let scale = if scale == 0.0 && sorted.len() < errors.len() {f64::INFINITY} else {scale};
+
+    let mut color_array = vec![vec![Color::new((0.0, 0.0, 0.0)); h]; w];
+    for (i, e) in errors.iter().enumerate() {
+        let (x, y) = (i % w, i / w);
+        let t = if !e.is_finite() {1.0} else if scale > 0.0 {e / scale} else {0.0};
+        color_array[x][h - 1 - y] = false_color(t);
+    }
+    (Screen::new(color_array, w as i32, h as i32), scale)
+}
@@ -0,0 +1,210 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::ColorTraits;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::tile::Tile;
+use crate::gfx::tile::TileTraits;
+
+// B3 spline taps used by every level of the a-trous wavelet transform
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+const EPSILON: f64 = 1e-4;
+
+// Per-pixel guide buffers gathered from the primary hit of every sample
+pub struct FeatureBuffers {
+    albedo: Vec<Vec<Color>>,
+    normal: Vec<Vec<Vec3>>,
+    depth: Vec<Vec<f64>>,
+    // running sums of demodulated luminance and its square
+    moments: Vec<Vec<(f64, f64)>>,
+    samples: Vec<Vec<i32>>,
+    image_width: i32,
+    image_height: i32
+}
+
+pub trait FeatureBuffersTraits {
+    fn new(image_width: i32, image_height: i32) -> Self;
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    fn add_sample(&mut self, x: usize, y: usize, radiance: Color, albedo: Color, normal: Vec3, depth: f64);
+
+    fn albedo(&self, x: usize, y: usize) -> Color;
+    fn normal(&self, x: usize, y: usize) -> Vec3;
+    fn depth(&self, x: usize, y: usize) -> f64;
+    fn variance(&self, x: usize, y: usize) -> f64;
+}
+
+impl FeatureBuffersTraits for FeatureBuffers {
+    fn new(image_width: i32, image_height: i32) -> Self {
+        let (w, h) = (image_width as usize, image_height as usize);
+        let zero = Vec3::new((0.0, 0.0, 0.0));
+        FeatureBuffers {
+            albedo: vec![vec![zero; h]; w],
+            normal: vec![vec![zero; h]; w],
+            depth: vec![vec![0.0; h]; w],
+            moments: vec![vec![(0.0, 0.0); h]; w],
+            samples: vec![vec![0; h]; w],
+            image_width,
+            image_height
+        }
+    }
+
+    fn width(&self) -> i32 {
+        self.image_width
+    }
+
+    fn height(&self) -> i32 {
+        self.image_height
+    }
+
+    fn add_sample(&mut self, x: usize, y: usize, radiance: Color, albedo: Color, normal: Vec3, depth: f64) {
+        self.albedo[x][y] += albedo;
+        self.normal[x][y] += normal;
+        self.depth[x][y] += depth;
+        let l = demodulate(radiance, albedo).luminance();
+        let (m1, m2) = self.moments[x][y];
+        self.moments[x][y] = (m1 + l, m2 + l * l);
+        self.samples[x][y] += 1;
+    }
+
+    fn albedo(&self, x: usize, y: usize) -> Color {
+        let n = self.samples[x][y].max(1) as f64;
+        self.albedo[x][y] / n
+    }
+
+    fn normal(&self, x: usize, y: usize) -> Vec3 {
+        let n = self.normal[x][y];
+        if n.length_squared() > 0.0 {n.unitize()} else {n}
+    }
+
+    fn depth(&self, x: usize, y: usize) -> f64 {
+        let n = self.samples[x][y].max(1) as f64;
+        self.depth[x][y] / n
+    }
+
+    // Variance of the pixel mean, which is the noise level left in the image
+    fn variance(&self, x: usize, y: usize) -> f64 {
+        let n = self.samples[x][y];
+        if n < 2 {
+            return 0.0;
+        }
+        let n = n as f64;
+        let (m1, m2) = self.moments[x][y];
+        let mean = m1 / n;
+        (m2 / n - mean * mean).max(0.0) / n
+    }
+}
+
+pub struct DenoiseSettings {
+    pub iterations: i32,
+    pub sigma_color: f64,
+    pub sigma_normal: f64,
+    pub sigma_depth: f64,
+    pub sigma_albedo: f64,
+    // SVGF-style luminance weighting scaled by the estimated per-pixel noise
+    pub variance_guided: bool,
+    // pixels outside are neither filtered nor used as neighbours, for a render
+    // that left them black. None is the whole image.
+    pub region: Option<Tile>
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings {
+            iterations: 5,
+            sigma_color: 0.5,
+            sigma_normal: 64.0,
+            sigma_depth: 0.1,
+            sigma_albedo: 0.1,
+            variance_guided: false,
+            region: None
+        }
+    }
+}
+
+fn demodulate(c: Color, albedo: Color) -> Color {
+    Color::new((c.x() / albedo.x().max(EPSILON),
+        c.y() / albedo.y().max(EPSILON),
+        c.z() / albedo.z().max(EPSILON)))
+}
+
+// Edge-avoiding a-trous filter over linear colors laid out as color[x][y].
+// Lighting is filtered separately from surface albedo so texture detail survives.
+pub fn atrous(color: &[Vec<Color>], features: &FeatureBuffers, settings: &DenoiseSettings) -> Vec<Vec<Color>> {
+    let w = features.width() as usize;
+    let h = features.height() as usize;
+    let image = Tile::image(w as i32, h as i32);
+    let region = settings.region.map_or(image, |region| region.intersection(&image));
+
+    let mut current: Vec<Vec<Color>> = (0..w)
+        .map(|x| (0..h).map(|y| demodulate(color[x][y], features.albedo(x, y))).collect())
+        .collect();
+    let mut variance: Vec<Vec<f64>> = (0..w)
+        .map(|x| (0..h).map(|y| features.variance(x, y)).collect())
+        .collect();
+
+    for i in 0..settings.iterations {
+        let step = 1i64 << i;
+        // the plain bilateral range shrinks at coarser levels, as in Dammertz et al.
+        let sigma_color = settings.sigma_color / (1 << i) as f64;
+        let mut next = current.clone();
+        let mut next_variance = variance.clone();
+
+        for x in 0..w {
+            for y in 0..h {
+                if !region.contains(x as i32, y as i32) {
+                    continue;
+                }
+                let cp = current[x][y];
+                let np = features.normal(x, y);
+                let zp = features.depth(x, y);
+                let ap = features.albedo(x, y);
+                let lp = cp.luminance();
+                let sigma_l = settings.sigma_color * variance[x][y].sqrt() + EPSILON;
+
+                let mut sum_w = 0.0;
+                let mut sum_v = 0.0;
+                let mut sum_c = Color::new((0.0, 0.0, 0.0));
+                for (dx, kx) in KERNEL.iter().enumerate() {
+                    for (dy, ky) in KERNEL.iter().enumerate() {
+                        let qx = x as i64 + (dx as i64 - 2) * step;
+                        let qy = y as i64 + (dy as i64 - 2) * step;
+                        if !region.contains(qx as i32, qy as i32) {
+                            continue;
+                        }
+                        let (qx, qy) = (qx as usize, qy as usize);
+                        let cq = current[qx][qy];
+
+                        let w_n = np.dot(features.normal(qx, qy)).max(0.0).powf(settings.sigma_normal);
+                        let zq = features.depth(qx, qy);
+                        let w_z = (-(zp - zq).abs() / (settings.sigma_depth * zp.max(zq) + EPSILON)).exp();
+                        let w_a = (-(ap - features.albedo(qx, qy)).length_squared()
+                            / (settings.sigma_albedo * settings.sigma_albedo)).exp();
+                        let w_l = if settings.variance_guided {
+                            (-(lp - cq.luminance()).abs() / sigma_l).exp()
+                        } else {
+                            (-(cp - cq).length_squared() / (sigma_color * sigma_color + EPSILON)).exp()
+                        };
+
+                        let weight = kx * ky * w_n * w_z * w_a * w_l;
+                        sum_c += weight * cq;
+                        sum_v += weight * weight * variance[qx][qy];
+                        sum_w += weight;
+                    }
+                }
+                if sum_w > 0.0 {
+                    next[x][y] = sum_c / sum_w;
+                    next_variance[x][y] = sum_v / (sum_w * sum_w);
+                }
+            }
+        }
+        current = next;
+        variance = next_variance;
+    }
+
+    (0..w)
+        .map(|x| (0..h).map(|y| {
+            if region.contains(x as i32, y as i32) {current[x][y] * features.albedo(x, y)} else {color[x][y]}
+        }).collect())
+        .collect()
+}
@@ -0,0 +1,380 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::film::Film;
+use crate::gfx::film::FilmTraits;
+use crate::gfx::film::TileResult;
+
+use crate::gfx::integrator::ColorMode;
+
+use crate::gfx::renderer::Renderer;
+use crate::gfx::renderer::RendererTraits;
+use crate::gfx::renderer::RenderSettings;
+use crate::gfx::renderer::RenderSettingsTraits;
+
+use crate::gfx::scene;
+
+use crate::gfx::screen::Screen;
+
+use crate::gfx::stats::RenderStats;
+
+use crate::gfx::tile::Tile;
+use crate::gfx::tile::TileTraits;
+
+// Coordinator and workers speak a line based protocol, with the sample sums
+// of a result following its line as little endian f64 triples:
+//   worker       HELLO rust_tracer 1
+//   coordinator  JOB <scene> <aspect ratio bits> <width> <height> <spp> <depth> <seed> <rgb|spectral>
+//   coordinator  TILE <x0> <y0> <x1> <y1>
+//   worker       RESULT <x0> <y0> <x1> <y1> <spp> <primary> <secondary> <shadow> <tests> <box tests>
+//   coordinator  DONE
+// <scene> is the name of a preset from scene::by_name, not a scene file: scenes
+// are built in code and there is no file format to send, so every worker has to
+// run a build with the same presets. Scenes outside them can't be distributed.
+const HELLO: &str = "HELLO rust_tracer 1";
+
+// How long a worker may take over one tile before it counts as dead
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+// Tiles handed out when the job's settings don't pick a size
+const DEFAULT_TILE_SIZE: i32 = 32;
+// Time a new connection has to introduce itself
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// What every worker renders: a scene preset, which each machine builds from its
+// own copy of scene::by_name, and the settings to render it with. The region and tiling only
+// matter to the coordinator, which hands out the tiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub scene: String,
+    pub aspect_ratio: f64,
+    pub settings: RenderSettings
+}
+
+impl Job {
+    fn to_line(&self) -> String {
+        let s = &self.settings;
+        let mode = match s.mode {
+            ColorMode::Rgb => "rgb",
+            ColorMode::Spectral => "spectral"
+        };
+        format!("JOB {} {:x} {} {} {} {} {} {}", self.scene, self.aspect_ratio.to_bits(), s.width, s.height,
+            s.samples_per_pixel, s.max_depth, s.seed, mode)
+    }
+
+    fn from_line(line: &str) -> io::Result<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 9 || fields[0] != "JOB" {
+            return Err(invalid(format!("expected a job, got {:?}", line)));
+        }
+        let number = |index: usize| fields[index].parse::<i32>().map_err(|_| invalid(format!("bad number in {:?}", line)));
+        let aspect_bits = u64::from_str_radix(fields[2], 16).map_err(|_| invalid(format!("bad aspect ratio in {:?}", line)))?;
+        let mode = match fields[8] {
+            "rgb" => ColorMode::Rgb,
+            "spectral" => ColorMode::Spectral,
+            other => return Err(invalid(format!("unknown color mode {}", other)))
+        };
+        let settings = RenderSettings {
+            width: number(3)?,
+            height: number(4)?,
+            samples_per_pixel: number(5)?,
+            max_depth: number(6)?,
+            seed: fields[7].parse().map_err(|_| invalid(format!("bad seed in {:?}", line)))?,
+            mode,
+            ..RenderSettings::default()
+        };
+        Ok(Job { scene: fields[1].to_string(), aspect_ratio: f64::from_bits(aspect_bits), settings })
+    }
+}
+
+// What a distributed render produced
+pub struct DistributedOutput {
+    pub screen: Screen,
+    pub stats: RenderStats,
+    // workers that connected at some point
+    pub workers: usize,
+    // tiles handed out again after their worker died or timed out
+    pub reassigned: usize
+}
+
+// Tiles waiting for a worker, and how many are still to come back
+struct Queue {
+    pending: VecDeque<Tile>,
+    outstanding: usize
+}
+
+enum Event {
+    Joined,
+    Finished(TileResult),
+    Lost
+}
+
+// Hands out tiles of a job to the workers that connect and merges what they
+// send back. A tile whose worker disconnects or times out goes back in the
+// queue for the next free worker. Workers may join at any time.
+pub struct Coordinator {
+    listener: TcpListener,
+    job: Job,
+    timeout: Duration
+}
+
+pub trait CoordinatorTraits {
+    fn bind<A: ToSocketAddrs>(address: A, job: Job) -> io::Result<Self> where Self: Sized;
+    // Longest a worker may spend on one tile
+    fn with_timeout(self, timeout: Duration) -> Self where Self: Sized;
+    fn address(&self) -> io::Result<SocketAddr>;
+    // Blocks until every tile is back. on_tile hears the number of tiles done
+    // and the total after each merge.
+    fn run<F: FnMut(usize, usize)>(self, on_tile: F) -> io::Result<DistributedOutput> where Self: Sized;
+}
+
+impl CoordinatorTraits for Coordinator {
+    fn bind<A: ToSocketAddrs>(address: A, job: Job) -> io::Result<Self> {
+        if scene::by_name(&job.scene, job.aspect_ratio).is_none() {
+            return Err(invalid(format!("unknown scene {}", job.scene)));
+        }
+        Ok(Coordinator { listener: TcpListener::bind(address)?, job, timeout: DEFAULT_TIMEOUT })
+    }
+
+    fn with_timeout(self, timeout: Duration) -> Self {
+        Coordinator { timeout, ..self }
+    }
+
+    fn address(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    fn run<F: FnMut(usize, usize)>(self, mut on_tile: F) -> io::Result<DistributedOutput> {
+        let settings = self.job.settings;
+        let RenderSettings { width, height, .. } = settings;
+        // a single row is too little work for a round trip to a worker
+        let tiles = RenderSettings { tile_size: Some(settings.tile_size.unwrap_or(DEFAULT_TILE_SIZE)), ..settings }.tiles();
+        let total = tiles.len();
+        let queue = Arc::new((Mutex::new(Queue { pending: tiles.into_iter().collect(), outstanding: total }), Condvar::new()));
+        let address = self.listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let (events, received) = mpsc::channel();
+
+        let acceptor = {
+            let (queue, stop, job, timeout) = (queue.clone(), stop.clone(), self.job.clone(), self.timeout);
+            let listener = self.listener;
+            thread::spawn(move || {
+                let mut handlers: Vec<JoinHandle<()>> = Vec::new();
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let (queue, job, events) = (queue.clone(), job.clone(), events.clone());
+                        handlers.push(thread::spawn(move || serve_worker(stream, &job, timeout, &queue, &events)));
+                    }
+                }
+                for handler in handlers {
+                    let _ = handler.join();
+                }
+            })
+        };
+
+        let mut film = Film::new(width, height);
+        let (mut stats, mut workers, mut reassigned, mut done) = (RenderStats::default(), 0, 0, 0);
+        while done < total {
+            match received.recv() {
+                Ok(Event::Joined) => workers += 1,
+                Ok(Event::Lost) => reassigned += 1,
+                Ok(Event::Finished(result)) => {
+                    film.add_tile(&result);
+                    stats = stats + result.stats;
+                    done += 1;
+                    let (lock, changed) = &*queue;
+                    lock.lock().unwrap().outstanding -= 1;
+                    changed.notify_all();
+                    on_tile(done, total);
+                }
+                Err(_) => return Err(io::Error::other("lost every worker thread"))
+            }
+        }
+
+        // idle workers are told they are done, then the accept loop is woken to see the flag
+        stop.store(true, Ordering::Relaxed);
+        let _ = TcpStream::connect(address);
+        let _ = acceptor.join();
+        Ok(DistributedOutput { screen: film.screen(), stats, workers, reassigned })
+    }
+}
+
+// Talks to one worker until the job is done or the worker is lost. A worker
+// that answers with anything but the job's samples per pixel counts as lost,
+// its pixels would weigh differently from everyone else's.
+fn serve_worker(stream: TcpStream, job: &Job, timeout: Duration, queue: &(Mutex<Queue>, Condvar), events: &mpsc::Sender<Event>) {
+    let handshake = || -> io::Result<Connection> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let mut connection = Connection::new(stream.try_clone()?)?;
+        if connection.read_line()? != HELLO {
+            return Err(invalid("not a rust_tracer worker".to_string()));
+        }
+        connection.write_line(&job.to_line())?;
+        stream.set_read_timeout(Some(timeout))?;
+        Ok(connection)
+    };
+    let mut connection = match handshake() {
+        Ok(connection) => connection,
+        Err(_) => return
+    };
+    let _ = events.send(Event::Joined);
+
+    let (lock, changed) = queue;
+    loop {
+        let tile = {
+            let mut queue = changed.wait_while(lock.lock().unwrap(), |queue| queue.pending.is_empty() && queue.outstanding > 0).unwrap();
+            match queue.pending.pop_front() {
+                Some(tile) => tile,
+                None => {
+                    let _ = connection.write_line("DONE");
+                    return;
+                }
+            }
+        };
+        match connection.render(tile, job.settings.samples_per_pixel) {
+            Ok(result) => {
+                let _ = events.send(Event::Finished(result));
+            }
+            Err(_) => {
+                lock.lock().unwrap().pending.push_front(tile);
+                changed.notify_all();
+                let _ = events.send(Event::Lost);
+                return;
+            }
+        }
+    }
+}
+
+// Connects to a coordinator and renders the tiles it hands out until it says
+// the job is done. Returns how many tiles this worker rendered.
+pub fn run_worker<A: ToSocketAddrs>(address: A) -> io::Result<usize> {
+    let mut connection = Connection::new(TcpStream::connect(address)?)?;
+    connection.write_line(HELLO)?;
+    let job = Job::from_line(&connection.read_line()?)?;
+    let scene = scene::by_name(&job.scene, job.aspect_ratio)
+        .ok_or_else(|| invalid(format!("unknown scene {}", job.scene)))?;
+    let mut renderer = Renderer::new(&scene, job.settings);
+    let mut rendered = 0;
+    loop {
+        let line = connection.read_line()?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.first() {
+            Some(&"TILE") => {
+                let tile = parse_tile(&fields[1..])?;
+                let result = renderer.render_tile(tile).ok_or_else(|| io::Error::other("render cancelled"))?;
+                connection.send_result(&result)?;
+                rendered += 1;
+            }
+            Some(&"DONE") => return Ok(rendered),
+            _ => return Err(invalid(format!("unexpected message {:?}", line)))
+        }
+    }
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Connection { reader: BufReader::new(stream.try_clone()?), writer: stream })
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    // Sends tile to the worker and waits for its result, which has to be samples_per_pixel deep
+    fn render(&mut self, tile: Tile, samples_per_pixel: i32) -> io::Result<TileResult> {
+        self.write_line(&format!("TILE {} {} {} {}", tile.x0, tile.y0, tile.x1, tile.y1))?;
+        let line = self.read_line()?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 11 || fields[0] != "RESULT" || parse_tile(&fields[1..5])? != tile {
+            return Err(invalid(format!("expected the result of {:?}, got {:?}", tile, line)));
+        }
+        let numbers = fields[5..].iter().map(|field| field.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|_| invalid(format!("bad number in {:?}", line)))?;
+        if numbers[0] != samples_per_pixel as u64 {
+            return Err(invalid(format!("expected {} samples per pixel, got {:?}", samples_per_pixel, line)));
+        }
+        let stats = RenderStats {
+            primary_rays: numbers[1],
+            secondary_rays: numbers[2],
+            shadow_rays: numbers[3],
+            intersection_tests: numbers[4],
+            box_tests: numbers[5]
+        };
+        let mut bytes = vec![0u8; tile.pixels() * 24];
+        self.reader.read_exact(&mut bytes)?;
+        let value = |chunk: &[u8]| {
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(chunk);
+            f64::from_le_bytes(raw)
+        };
+        let sums = bytes.chunks(24)
+            .map(|pixel| Color::new((value(&pixel[0..8]), value(&pixel[8..16]), value(&pixel[16..24]))))
+            .collect();
+        Ok(TileResult { tile, samples_per_pixel: numbers[0] as i32, sums, stats })
+    }
+
+    fn send_result(&mut self, result: &TileResult) -> io::Result<()> {
+        let (t, s) = (result.tile, result.stats);
+        let mut message = format!("RESULT {} {} {} {} {} {} {} {} {} {}\n", t.x0, t.y0, t.x1, t.y1, result.samples_per_pixel,
+            s.primary_rays, s.secondary_rays, s.shadow_rays, s.intersection_tests, s.box_tests).into_bytes();
+        for sum in &result.sums {
+            for channel in [sum.x(), sum.y(), sum.z()].iter() {
+                message.extend_from_slice(&channel.to_le_bytes());
+            }
+        }
+        self.writer.write_all(&message)?;
+        self.writer.flush()
+    }
+}
+
+fn parse_tile(fields: &[&str]) -> io::Result<Tile> {
+    let corners = fields.iter().take(4).map(|field| field.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| invalid(format!("bad tile {:?}", fields)))?;
+    match corners[..] {
+        [x0, y0, x1, y1] => Ok(Tile::new(x0, y0, x1, y1)),
+        _ => Err(invalid(format!("bad tile {:?}", fields)))
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,89 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::screen::Screen;
+use crate::gfx::screen::ScreenTraits;
+
+use crate::gfx::stats::RenderStats;
+
+use crate::gfx::tile::Tile;
+use crate::gfx::tile::TileTraits;
+
+// Sample sums of every pixel of one tile, in Tile::coordinates order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileResult {
+    pub tile: Tile,
+    pub samples_per_pixel: i32,
+    pub sums: Vec<Color>,
+    pub stats: RenderStats
+}
+
+// Running sums of the samples through every pixel. Renders add to it a pass
+// or a tile at a time, and it resolves to a Screen whenever one is wanted.
+pub struct Film {
+    width: i32,
+    height: i32,
+    sums: Vec<Color>,
+    counts: Vec<i32>
+}
+
+pub trait FilmTraits {
+    fn new(width: i32, height: i32) -> Self;
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    fn samples(&self, i: i32, j: i32) -> i32;
+    fn add_samples(&mut self, i: i32, j: i32, sum: Color, samples: i32);
+    fn add_tile(&mut self, result: &TileResult);
+    // Average of every pixel's samples, gamma encoded. Pixels without any stay black.
+    fn screen(&self) -> Screen;
+}
+
+impl Film {
+    fn index(&self, i: i32, j: i32) -> usize {
+        (j * self.width + i) as usize
+    }
+}
+
+impl FilmTraits for Film {
+    fn new(width: i32, height: i32) -> Self {
+        let pixels = (width.max(0) * height.max(0)) as usize;
+        Film { width, height, sums: vec![Color::new((0.0, 0.0, 0.0)); pixels], counts: vec![0; pixels] }
+    }
+
+    fn width(&self) -> i32 {
+        self.width
+    }
+
+    fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn samples(&self, i: i32, j: i32) -> i32 {
+        self.counts[self.index(i, j)]
+    }
+
+    fn add_samples(&mut self, i: i32, j: i32, sum: Color, samples: i32) {
+        let index = self.index(i, j);
+        self.sums[index] += sum;
+        self.counts[index] += samples;
+    }
+
+    fn add_tile(&mut self, result: &TileResult) {
+        for ((i, j), sum) in result.tile.coordinates().zip(&result.sums) {
+            self.add_samples(i, j, *sum, result.samples_per_pixel);
+        }
+    }
+
+    fn screen(&self) -> Screen {
+        let mut screen = Screen::empty_screen(self.width, self.height);
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let index = self.index(i, j);
+                if self.counts[index] > 0 {
+                    screen.color_pixel(i as usize, j as usize, self.sums[index], self.counts[index]);
+                }
+            }
+        }
+        screen
+    }
+}
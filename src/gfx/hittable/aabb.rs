@@ -0,0 +1,76 @@
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::stats;
+
+// Axis aligned bounding box, used to skip objects a ray cannot reach
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    min: Point3,
+    max: Point3
+}
+
+pub trait AabbTraits {
+    fn new(a: Point3, b: Point3) -> Self;
+    fn min(&self) -> Point3;
+    fn max(&self) -> Point3;
+    fn union(&self, other: Aabb) -> Self;
+    fn corners(&self) -> [Point3; 8];
+    // Whether r passes through the box somewhere in (t_min, t_max). Rays that
+    // only touch it, along a face or through an edge, count as hits.
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool;
+}
+
+impl AabbTraits for Aabb {
+    fn new(a: Point3, b: Point3) -> Self {
+        Aabb {
+            min: Point3::new((a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()))),
+            max: Point3::new((a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())))
+        }
+    }
+
+    fn min(&self) -> Point3 {
+        self.min
+    }
+
+    fn max(&self) -> Point3 {
+        self.max
+    }
+
+    fn union(&self, other: Aabb) -> Self {
+        Aabb::new(Aabb::new(self.min, other.min).min, Aabb::new(self.max, other.max).max)
+    }
+
+    fn corners(&self) -> [Point3; 8] {
+        let (a, b) = (self.min, self.max);
+        [Point3::new((a.x(), a.y(), a.z())), Point3::new((b.x(), a.y(), a.z())),
+            Point3::new((a.x(), b.y(), a.z())), Point3::new((b.x(), b.y(), a.z())),
+            Point3::new((a.x(), a.y(), b.z())), Point3::new((b.x(), a.y(), b.z())),
+            Point3::new((a.x(), b.y(), b.z())), Point3::new((b.x(), b.y(), b.z()))]
+    }
+
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        stats::count_box_test();
+        let (origin, direction) = (r.origin(), r.direction());
+        let slabs = [(origin.x(), direction.x(), self.min.x(), self.max.x()),
+            (origin.y(), direction.y(), self.min.y(), self.max.y()),
+            (origin.z(), direction.z(), self.min.z(), self.max.z())];
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        for (o, d, min, max) in slabs.iter() {
+            let inv = 1.0 / d;
+            // 0 * inf is NaN for a ray lying in a face, max and min skip it so the slab doesn't cull
+            let (t0, t1) = ((min - o) * inv, (max - o) * inv);
+            let (t0, t1) = if inv < 0.0 {(t1, t0)} else {(t0, t1)};
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            // equal on a slab of zero thickness, which the ray does cross
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,65 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::ray::Ray;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::hittable::aabb::Aabb;
+use crate::gfx::texture::Texture;
+
+// Cut-out limit on how many transparent layers of one object a ray walks through
+const MAX_CUTOUT_LAYERS: i32 = 64;
+
+// Alpha tested geometry such as foliage cards and fences. The opacity is read
+// from the first channel of the texture at the hit's (u, v): 0 is cut out,
+// 1 is solid and anything in between is kept with that probability.
+pub struct AlphaMaskData {
+    object: Rc<dyn Hittable>,
+    opacity: Rc<dyn Texture>
+}
+
+pub trait AlphaMask : Hittable {
+    fn new(object: Rc<dyn Hittable>, opacity: Rc<dyn Texture>) -> Self;
+}
+
+impl AlphaMask for AlphaMaskData {
+    fn new(object: Rc<dyn Hittable>, opacity: Rc<dyn Texture>) -> Self {
+        AlphaMaskData { object, opacity }
+    }
+}
+
+impl AlphaMaskData {
+    fn opaque(&self, rec: &HitRecord) -> bool {
+        let alpha = self.opacity.scalar(rec.u(), rec.v(), rec.p());
+        if alpha >= 1.0 {
+            return true;
+        }
+        if alpha <= 0.0 {
+            return false;
+        }
+        let mut rng = random::rng();
+        rng.gen_range(0.0, 1.0) < alpha
+    }
+}
+
+impl Hittable for AlphaMaskData {
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // keep searching beyond every hit that lands on a transparent texel
+        let mut t_min = t_min;
+        for _ in 0..MAX_CUTOUT_LAYERS {
+            let rec = self.object.hit(r, t_min, t_max)?;
+            if self.opaque(&rec) {
+                return Some(rec);
+            }
+            t_min = rec.t();
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+}
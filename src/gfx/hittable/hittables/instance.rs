@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::hittable::aabb::Aabb;
+use crate::gfx::hittable::aabb::AabbTraits;
+
+use crate::degrees_to_radians;
+
+// Shared geometry placed in the world by a uniform scale, then a rotation
+// about +Y, then a translation. Rays are taken into the object's own space
+// rather than copying it, so one object can be instanced many times.
+pub struct InstanceData {
+    object: Rc<dyn Hittable>,
+    offset: Vec3,
+    sin_theta: f64,
+    cos_theta: f64,
+    scale: f64,
+    // the object's own box, rays that miss it skip the object entirely
+    bbox: Option<Aabb>
+}
+
+pub trait Instance : Hittable {
+    // angle is in degrees, counterclockwise looking down -Y
+    fn new(object: Rc<dyn Hittable>, offset: Vec3, angle: f64, scale: f64) -> Self;
+}
+
+impl Instance for InstanceData {
+    fn new(object: Rc<dyn Hittable>, offset: Vec3, angle: f64, scale: f64) -> Self {
+        let theta = degrees_to_radians(angle);
+        let bbox = object.bounding_box();
+        InstanceData { object, offset, sin_theta: theta.sin(), cos_theta: theta.cos(), scale, bbox }
+    }
+}
+
+impl InstanceData {
+    fn rotate(&self, v: Vec3, sin_theta: f64) -> Vec3 {
+        Vec3::new((self.cos_theta * v.x() + sin_theta * v.z(), v.y(), -sin_theta * v.x() + self.cos_theta * v.z()))
+    }
+
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        self.rotate(v, self.sin_theta)
+    }
+
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        self.rotate(v, -self.sin_theta)
+    }
+
+    // Same ray in object space, scaling the direction along with the origin keeps t unchanged
+    fn object_ray(&self, r: &Ray) -> Ray {
+        let origin = self.to_object(r.origin() - self.offset) / self.scale;
+        let direction = self.to_object(r.direction()) / self.scale;
+        Ray::new(origin, direction).with_wavelength(r.wavelength())
+    }
+
+    fn may_hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.bbox.is_none_or(|bbox| bbox.hit(r, t_min, t_max))
+    }
+}
+
+impl Hittable for InstanceData {
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let object_ray = self.object_ray(&r);
+        if !self.may_hit(&object_ray, t_min, t_max) {
+            return None;
+        }
+        let mut rec = self.object.hit(object_ray, t_min, t_max)?;
+        rec.set_p(self.scale * self.to_world(rec.p()) + self.offset);
+        // a uniform scale leaves normals alone, the front face test is unchanged by rotation
+        let front = rec.front();
+        let normal = self.to_world(rec.normal());
+        rec.set_face_normal(r, if front {normal} else {-normal});
+        rec.set_tangents(self.scale * self.to_world(rec.dpdu()), self.scale * self.to_world(rec.dpdv()));
+        Some(rec)
+    }
+
+    fn occluded(&self, r: Ray, t_min: f64, t_max: f64) -> bool {
+        let object_ray = self.object_ray(&r);
+        self.may_hit(&object_ray, t_min, t_max) && self.object.occluded(object_ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = self.bbox?.corners();
+        let world = |p: Vec3| self.scale * self.to_world(p) + self.offset;
+        Some(corners.iter().fold(Aabb::new(world(corners[0]), world(corners[0])), |acc, &p| acc.union(Aabb::new(world(p), world(p)))))
+    }
+}
@@ -1,9 +1,25 @@
-use crate::basic_types::ray::Ray;
-use crate::gfx::hittable::HitRecord;
-
-pub trait Hittable {
-    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> (Option<HitRecord>, bool);
-}
-
-pub mod hittable_list;
-pub mod sphere;
\ No newline at end of file
+use crate::basic_types::ray::Ray;
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::aabb::Aabb;
+
+pub trait Hittable {
+    // Closest intersection of r with t in (t_min, t_max)
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+
+    // Whether anything blocks r between t_min and t_max. Stops at the first
+    // intersection found and builds no HitRecord where the primitive allows it.
+    fn occluded(&self, r: Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit(r, t_min, t_max).is_some()
+    }
+
+    // Box enclosing the whole object, None when it is unbounded or unknown
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+pub mod hittable_list;
+pub mod sphere;
+pub mod alpha_mask;
+pub mod quad;
+pub mod instance;
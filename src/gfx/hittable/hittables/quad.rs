@@ -0,0 +1,120 @@
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::hittable::aabb::Aabb;
+use crate::gfx::hittable::aabb::AabbTraits;
+use crate::gfx::hittable::hittables::hittable_list::HittableList;
+use crate::gfx::hittable::hittables::hittable_list::HittableListTrait;
+
+use crate::gfx::material::Material;
+
+use crate::gfx::stats;
+
+const QUAD_BOX_PADDING: f64 = 1e-4;
+
+// Parallelogram with corner q and edges u and v. The front faces along u x v,
+// and (u, v) runs over [0, 1] along the two edges.
+pub struct QuadData {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    // plane offset along normal
+    d: f64,
+    // dotted with a point relative to q these give its (u, v)
+    u_axis: Vec3,
+    v_axis: Vec3,
+    mat_ptr: Rc<dyn Material>
+}
+
+pub trait Quad : Hittable {
+    fn new(q: Point3, u: Vec3, v: Vec3, mat_ptr: Rc<dyn Material>) -> Self;
+}
+
+impl Quad for QuadData {
+    fn new(q: Point3, u: Vec3, v: Vec3, mat_ptr: Rc<dyn Material>) -> Self {
+        let n = u.cross(v);
+        let normal = n.unitize();
+        let d = normal.dot(q);
+        let w = n / n.dot(n);
+        QuadData { q, u, v, normal, d, u_axis: v.cross(w), v_axis: w.cross(u), mat_ptr }
+    }
+}
+
+impl QuadData {
+    // Ray parameter and (u, v) of the crossing with the quad, if any
+    fn intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+        stats::count_intersection_test();
+        let denom = self.normal.dot(r.direction());
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.d - self.normal.dot(r.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let planar = r.at(t) - self.q;
+        let alpha = planar.dot(self.u_axis);
+        let beta = planar.dot(self.v_axis);
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some((t, alpha, beta))
+    }
+}
+
+impl Hittable for QuadData {
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, u, v) = self.intersect(&r, t_min, t_max)?;
+        let mut rec = HitRecord::new(self.mat_ptr.clone());
+        rec.set_t(t);
+        rec.set_p(r.at(t));
+        rec.set_face_normal(r, self.normal);
+        rec.set_uv(u, v);
+        rec.set_tangents(self.u, self.v);
+        Some(rec)
+    }
+
+    fn occluded(&self, r: Ray, t_min: f64, t_max: f64) -> bool {
+        self.intersect(&r, t_min, t_max).is_some()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // padded so an axis aligned quad doesn't give a box of zero thickness
+        let pad = Vec3::new((QUAD_BOX_PADDING, QUAD_BOX_PADDING, QUAD_BOX_PADDING));
+        let bbox = Aabb::new(self.q, self.q + self.u + self.v).union(Aabb::new(self.q + self.u, self.q + self.v));
+        Some(Aabb::new(bbox.min() - pad, bbox.max() + pad))
+    }
+}
+
+// Axis aligned box spanning corners a and b, made of six outward facing quads
+pub fn cuboid(a: Point3, b: Point3, mat_ptr: Rc<dyn Material>) -> HittableList {
+    let min = Point3::new((a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())));
+    let max = Point3::new((a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())));
+    let dx = Vec3::new((max.x() - min.x(), 0.0, 0.0));
+    let dy = Vec3::new((0.0, max.y() - min.y(), 0.0));
+    let dz = Vec3::new((0.0, 0.0, max.z() - min.z()));
+
+    let mut sides = HittableList::new();
+    let faces = [
+        (Point3::new((min.x(), min.y(), max.z())), dx, dy),
+        (Point3::new((max.x(), min.y(), max.z())), -dz, dy),
+        (Point3::new((max.x(), min.y(), min.z())), -dx, dy),
+        (Point3::new((min.x(), min.y(), min.z())), dz, dy),
+        (Point3::new((min.x(), max.y(), max.z())), dx, -dz),
+        (Point3::new((min.x(), min.y(), min.z())), dx, dz)
+    ];
+    for (q, u, v) in faces.iter() {
+        sides.add(Rc::new(QuadData::new(*q, *u, *v, mat_ptr.clone())));
+    }
+    sides
+}
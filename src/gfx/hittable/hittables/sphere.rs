@@ -1,66 +1,112 @@
-use std::rc::Rc;
-
-use crate::basic_types::vec3::Point3;
-use crate::basic_types::vec3::Vec3Traits;
-
-use crate::basic_types::ray::Ray;
-use crate::basic_types::ray::RayTraits;
-
-use crate::gfx::hittable::HitRecord;
-use crate::gfx::hittable::HitRecordTraits;
-use crate::gfx::hittable::hittables::Hittable;
-
-use crate::gfx::material::Material;
-
-pub struct SphereData {
-    center: Point3,
-    radius: f64,
-    mat_ptr: Rc<dyn Material>
-}
-
-pub trait Sphere : Hittable {
-    fn new(cen: Point3, radius: f64, mat_ptr: Rc<dyn Material>) -> Self;
-}
-
-impl Sphere for SphereData {
-    fn new(center: Point3, radius: f64, mat_ptr: Rc<dyn Material>) -> Self {
-        SphereData { center, radius, mat_ptr }
-    }
-}
-
-impl Hittable for SphereData {
-    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> (Option<HitRecord>, bool) {
-        let oc = r.origin() - self.center;
-        let a = r.direction().length_squared();
-        let b = oc.dot(r.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
-        let discriminant = b*b - a * c;
-        
-
-        if discriminant >= 0.0 {
-            let root = discriminant.sqrt();
-            let temp = (-b - root) / a;
-            let mut rec = HitRecord::new(self.mat_ptr.clone());
-            if temp < t_max && temp > t_min {
-                rec.set_t(temp.clone());
-                let p = r.at(temp);
-                let outward_normal = (p - self.center) / self.radius;
-                rec.set_face_normal(r, outward_normal);
-                rec.set_p(p);
-                rec.set_mat_ptr(self.mat_ptr.clone());
-                return (Some(rec), true);
-            }
-
-            let temp = (-b + root) / a;
-            if temp < t_max && temp > t_min {
-                rec.set_t(temp.clone());
-                let p = r.at(temp);
-                let outward_normal = (p - self.center) / self.radius;
-                rec.set_face_normal(r, outward_normal);
-                rec.set_p(p);
-                return (Some(rec), true);
-            }
-        }
-        return (None, false);
-    }
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::hittable::aabb::Aabb;
+use crate::gfx::hittable::aabb::AabbTraits;
+
+use crate::gfx::material::Material;
+
+use crate::gfx::stats;
+
+pub struct SphereData {
+    center: Point3,
+    radius: f64,
+    mat_ptr: Rc<dyn Material>
+}
+
+pub trait Sphere : Hittable {
+    fn new(cen: Point3, radius: f64, mat_ptr: Rc<dyn Material>) -> Self;
+}
+
+impl Sphere for SphereData {
+    fn new(center: Point3, radius: f64, mat_ptr: Rc<dyn Material>) -> Self {
+        SphereData { center, radius, mat_ptr }
+    }
+}
+
+// Spherical coordinates of a point on the unit sphere, u around Y and v from -Y to +Y
+fn get_sphere_uv(p: Vec3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+    (phi / (2.0 * std::f64::consts::PI), theta / std::f64::consts::PI)
+}
+
+// Derivatives of the surface point with respect to the (u, v) of get_sphere_uv
+fn get_sphere_tangents(n: Vec3, radius: f64) -> (Vec3, Vec3) {
+    let pi = std::f64::consts::PI;
+    let dpdu = 2.0 * pi * radius * Vec3::new((n.z(), 0.0, -n.x()));
+    let sin_theta = (n.x() * n.x() + n.z() * n.z()).sqrt();
+    if sin_theta < 1e-8 {
+        // u is degenerate at the poles, any frame around the normal will do
+        return (2.0 * pi * radius * Vec3::new((0.0, 0.0, 1.0)), pi * radius * Vec3::new((1.0, 0.0, 0.0)));
+    }
+    let dpdv = pi * radius * Vec3::new((-n.x() * n.y() / sin_theta, sin_theta, -n.y() * n.z() / sin_theta));
+    (dpdu, dpdv)
+}
+
+impl Hittable for SphereData {
+    fn hit(&self, r: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        stats::count_intersection_test();
+        let oc = r.origin() - self.center;
+        let a = r.direction().length_squared();
+        let b = oc.dot(r.direction());
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = b*b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        // nearest root in range, the record is only built once we know there is a hit
+        let root = discriminant.sqrt();
+        let mut temp = (-b - root) / a;
+        if temp >= t_max || temp <= t_min {
+            temp = (-b + root) / a;
+            if temp >= t_max || temp <= t_min {
+                return None;
+            }
+        }
+
+        let mut rec = HitRecord::new(self.mat_ptr.clone());
+        rec.set_t(temp);
+        let p = r.at(temp);
+        let outward_normal = (p - self.center) / self.radius;
+        rec.set_face_normal(r, outward_normal);
+        let (u, v) = get_sphere_uv(outward_normal);
+        rec.set_uv(u, v);
+        let (dpdu, dpdv) = get_sphere_tangents(outward_normal, self.radius);
+        rec.set_tangents(dpdu, dpdv);
+        rec.set_p(p);
+        Some(rec)
+    }
+
+    fn occluded(&self, r: Ray, t_min: f64, t_max: f64) -> bool {
+        stats::count_intersection_test();
+        let oc = r.origin() - self.center;
+        let a = r.direction().length_squared();
+        let b = oc.dot(r.direction());
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = b*b - a * c;
+        if discriminant < 0.0 {
+            return false;
+        }
+        let root = discriminant.sqrt();
+        let near = (-b - root) / a;
+        let far = (-b + root) / a;
+        (near < t_max && near > t_min) || (far < t_max && far > t_min)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius.abs();
+        let extent = Vec3::new((r, r, r));
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
 }
\ No newline at end of file
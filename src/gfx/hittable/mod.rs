@@ -1,84 +1,130 @@
-use std::rc::Rc;
-use crate::basic_types::vec3::Point3;
-use crate::basic_types::vec3::Vec3;
-use crate::basic_types::vec3::Vec3Traits;
-
-use crate::basic_types::ray::Ray;
-use crate::basic_types::ray::RayTraits;
-
-use crate::gfx::material::Material;
-
-
-#[derive(Clone)]
-pub struct HitRecord {
-    p: Point3,
-    normal: Vec3,
-    mat_ptr: Rc<dyn Material>,
-    front_face: bool,
-    t: f64
-}
-
-pub trait HitRecordTraits {
-    fn new(mat_ptr: Rc<dyn Material>) -> Self;
-    fn p(&self) -> Point3;
-    fn normal(&self) -> Vec3;
-    fn front(&self) -> bool;
-    fn t(&self) -> f64;
-    fn mat_ptr(&self) -> Rc<dyn Material>;
-
-    fn set_p(&mut self, p: Point3);
-    fn set_t(&mut self, t: f64);
-    fn set_face_normal(&mut self, r: Ray, outward_normal: Vec3);
-    fn set_mat_ptr(&mut self, mat_ptr: Rc<dyn Material>);
-}
-
-
-impl HitRecordTraits for HitRecord {
-    fn new(mat_ptr: Rc<dyn Material>) -> Self {
-        let p = Point3::new((0.0,0.0,0.0));
-        let normal = Vec3::new((0.0, 0.0, 0.0));
-        HitRecord { p, normal, mat_ptr, front_face: false, t: 0.0 }
-    }
-
-    fn p(&self) -> Point3 {
-        self.p
-    }
-
-    fn normal(&self) -> Vec3 {
-        self.normal
-    }
-
-    fn front(&self) -> bool {
-        self.front_face
-    }
-
-    fn t(&self) -> f64 {
-        self.t
-    }
-
-    fn mat_ptr(&self) -> Rc<dyn Material> {
-        self.mat_ptr.clone()
-    }
-    
-    fn set_p(&mut self, p: Point3) {
-        self.p = p
-    }
-
-    fn set_t(&mut self, t: f64) {
-        self.t = t
-    }
-
-    fn set_face_normal(&mut self, r: Ray, outward_normal: Vec3) {
-        self.front_face = r.direction().dot(outward_normal) < 0.0;
-        self.normal = if self.front_face {outward_normal} else {-outward_normal};
-    }
-
-    fn set_mat_ptr(&mut self, mat_ptr: Rc<dyn Material>) {
-        self.mat_ptr = mat_ptr
-    }
-}
-
-
-pub mod hittables;
-
-
+use std::rc::Rc;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::material::Material;
+
+
+#[derive(Clone)]
+pub struct HitRecord {
+    p: Point3,
+    normal: Vec3,
+    // surface tangents along the u and v texture directions
+    dpdu: Vec3,
+    dpdv: Vec3,
+    mat_ptr: Rc<dyn Material>,
+    front_face: bool,
+    t: f64,
+    u: f64,
+    v: f64
+}
+
+pub trait HitRecordTraits {
+    fn new(mat_ptr: Rc<dyn Material>) -> Self;
+    fn p(&self) -> Point3;
+    fn normal(&self) -> Vec3;
+    fn dpdu(&self) -> Vec3;
+    fn dpdv(&self) -> Vec3;
+    fn front(&self) -> bool;
+    fn t(&self) -> f64;
+    fn u(&self) -> f64;
+    fn v(&self) -> f64;
+    fn mat_ptr(&self) -> Rc<dyn Material>;
+
+    fn set_p(&mut self, p: Point3);
+    fn set_t(&mut self, t: f64);
+    fn set_uv(&mut self, u: f64, v: f64);
+    fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3);
+    fn set_face_normal(&mut self, r: Ray, outward_normal: Vec3);
+    // Replace the normal used for shading, it must face the same side as the hit
+    fn set_shading_normal(&mut self, normal: Vec3);
+    fn set_mat_ptr(&mut self, mat_ptr: Rc<dyn Material>);
+}
+
+
+impl HitRecordTraits for HitRecord {
+    fn new(mat_ptr: Rc<dyn Material>) -> Self {
+        let p = Point3::new((0.0,0.0,0.0));
+        let normal = Vec3::new((0.0, 0.0, 0.0));
+        let dpdu = Vec3::new((0.0, 0.0, 0.0));
+        let dpdv = Vec3::new((0.0, 0.0, 0.0));
+        HitRecord { p, normal, dpdu, dpdv, mat_ptr, front_face: false, t: 0.0, u: 0.0, v: 0.0 }
+    }
+
+    fn p(&self) -> Point3 {
+        self.p
+    }
+
+    fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    fn dpdu(&self) -> Vec3 {
+        self.dpdu
+    }
+
+    fn dpdv(&self) -> Vec3 {
+        self.dpdv
+    }
+
+    fn front(&self) -> bool {
+        self.front_face
+    }
+
+    fn t(&self) -> f64 {
+        self.t
+    }
+
+    fn u(&self) -> f64 {
+        self.u
+    }
+
+    fn v(&self) -> f64 {
+        self.v
+    }
+
+    fn mat_ptr(&self) -> Rc<dyn Material> {
+        self.mat_ptr.clone()
+    }
+    
+    fn set_p(&mut self, p: Point3) {
+        self.p = p
+    }
+
+    fn set_t(&mut self, t: f64) {
+        self.t = t
+    }
+
+    fn set_uv(&mut self, u: f64, v: f64) {
+        self.u = u;
+        self.v = v
+    }
+
+    fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3) {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv
+    }
+
+    fn set_face_normal(&mut self, r: Ray, outward_normal: Vec3) {
+        self.front_face = r.direction().dot(outward_normal) < 0.0;
+        self.normal = if self.front_face {outward_normal} else {-outward_normal};
+    }
+
+    fn set_shading_normal(&mut self, normal: Vec3) {
+        self.normal = normal
+    }
+
+    fn set_mat_ptr(&mut self, mat_ptr: Rc<dyn Material>) {
+        self.mat_ptr = mat_ptr
+    }
+}
+
+
+pub mod hittables;
+pub mod aabb;
+
+
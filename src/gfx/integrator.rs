@@ -0,0 +1,230 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::basic_types::spectrum;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::light::Light;
+use crate::gfx::stats;
+
+// Fraction of the distance to a light that shadow rays stop short of
+const SHADOW_EPSILON: f64 = 1e-4;
+
+// How path throughput is carried, either as RGB triples or at a single
+// sampled wavelength per path so dispersive materials can split light
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorMode {
+    Rgb,
+    Spectral
+}
+
+// What the camera ray of a sample saw first, the guides of the denoiser. Taken
+// from the path being traced, so gathering them draws no extra random numbers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrimaryFeatures {
+    pub albedo: Color,
+    pub normal: Vec3,
+    // distance along the camera ray, 0 where it left the scene
+    pub depth: f64
+}
+
+impl PrimaryFeatures {
+    // Where the ray leaves the scene: the background as albedo, a normal facing back along it
+    fn missed(r: &Ray, background: &Background) -> Self {
+        PrimaryFeatures { albedo: background.color(r), normal: -r.direction().unitize(), depth: 0.0 }
+    }
+
+    // Surface of rec, the albedo is filled in once the path scatters off it
+    fn hit(&mut self, r: &Ray, rec: &HitRecord) {
+        *self = PrimaryFeatures { albedo: Color::new((1.0, 1.0, 1.0)), normal: rec.normal(), depth: rec.t() * r.direction().length() };
+    }
+}
+
+// What rays leaving the scene see
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Background {
+    Sky,
+    Solid(Color)
+}
+
+pub trait BackgroundTraits {
+    fn color(&self, r: &Ray) -> Color;
+}
+
+impl BackgroundTraits for Background {
+    fn color(&self, r: &Ray) -> Color {
+        match self {
+            Background::Sky => background(r),
+            Background::Solid(color) => *color
+        }
+    }
+}
+
+// Sky gradient seen by rays that leave the scene
+pub fn background(r: &Ray) -> Color {
+    // Normalize ray direction 
+    let unit_direction = r.direction().unitize();
+    // transform y coordinate to viewport coordinate system then scale to range [0,1]
+    let t = 0.5 * (unit_direction.y() + 1.0);
+    (1.0 - t) * Color::new((1.0, 1.0, 1.0)) + 
+        t*Color::new((0.5, 0.7, 1.0))
+}
+
+// A ray reaching the back face of an object travelled through its interior, so
+// give the medium filling it, if any, the chance to scatter the ray before it
+// gets to the boundary
+fn interior(r: &Ray, rec: &HitRecord, throughput: Color) -> (Color, Option<Ray>) {
+    let material = rec.mat_ptr();
+    match material.medium() {
+        Some(medium) if !rec.front() => medium.sample(r, rec.t(), throughput),
+        _ => (Color::new((1.0, 1.0, 1.0)), None)
+    }
+}
+
+// Whether anything in the world blocks the segment from p to a light distance away along wi
+fn occluded(world: &dyn Hittable, p: Point3, wi: Vec3, distance: f64) -> bool {
+    stats::count_shadow_ray();
+    world.occluded(Ray::new(p, wi), 0.001, distance * (1.0 - SHADOW_EPSILON))
+}
+
+// Scattering function and incident light for every light that reaches rec unblocked
+fn light_samples<'a>(r: &'a Ray, rec: &'a HitRecord, world: &'a dyn Hittable, lights: &'a [Rc<dyn Light>])
+    -> impl Iterator<Item = (Color, Color)> + 'a {
+    let material = rec.mat_ptr();
+    lights.iter().filter_map(move |light| {
+        let (li, wi, distance) = light.sample_li(rec.p());
+        if li.length_squared() == 0.0 {
+            return None;
+        }
+        let f = material.eval(r, rec, wi);
+        if f.length_squared() == 0.0 || occluded(world, rec.p(), wi, distance) {
+            return None;
+        }
+        Some((f, li))
+    })
+}
+
+// Light from the analytic lights leaving rec back along r after one bounce
+fn direct_light(r: &Ray, rec: &HitRecord, world: &dyn Hittable, lights: &[Rc<dyn Light>]) -> Color {
+    light_samples(r, rec, world, lights)
+        .fold(Color::new((0.0, 0.0, 0.0)), |total, (f, li)| total + f * li)
+}
+
+pub fn ray_color(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32) -> Color {
+    trace(r, world, lights, background, depth, Color::new((1.0, 1.0, 1.0)), None)
+}
+
+// throughput is the weight of the path up to r, which only guides sampling.
+// features, if given, learns about the first surface r hits.
+fn trace(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32, throughput: Color,
+    mut features: Option<&mut PrimaryFeatures>) -> Color {
+    if depth <= 0 {
+        return Color::new((0.0,0.0,0.0));
+    }
+    stats::count_ray();
+    if let Some(rec) = world.hit(r, 0.001, f64::INFINITY) {
+        if let Some(features) = features.as_deref_mut() {
+            features.hit(&r, &rec);
+        }
+        let (transmittance, scattered) = interior(&r, &rec, throughput);
+        if let Some(scattered) = scattered {
+            return transmittance * trace(scattered, world, lights, background, depth - 1, throughput * transmittance, None);
+        }
+        let material = rec.mat_ptr();
+        let direct = transmittance * (material.emitted(&rec) + direct_light(&r, &rec, world, lights));
+        let (attenuation, scattered, flag) = material.scatter(&r, &rec);
+        if let Some(features) = features {
+            features.albedo = attenuation;
+        }
+        if flag {
+            let weight = transmittance * attenuation;
+            return direct + weight * trace(scattered, world, lights, background, depth - 1, throughput * weight, None);
+        }
+        return direct;
+    }
+    if let Some(features) = features {
+        *features = PrimaryFeatures::missed(&r, background);
+    }
+    background.color(&r)
+}
+
+// Radiance at the wavelength carried by r, RGB albedos and the sky are upsampled
+fn spectral_radiance(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32,
+    mut features: Option<&mut PrimaryFeatures>) -> f64 {
+    let lambda = r.wavelength().unwrap_or(spectrum::LAMBDA_D);
+    if depth <= 0 {
+        return 0.0;
+    }
+    stats::count_ray();
+    if let Some(rec) = world.hit(r, 0.001, f64::INFINITY) {
+        if let Some(features) = features.as_deref_mut() {
+            features.hit(&r, &rec);
+        }
+        let (transmittance, scattered) = interior(&r, &rec, Color::new((1.0, 1.0, 1.0)));
+        let transmittance = spectrum::rgb_to_spectrum(transmittance, lambda);
+        if let Some(scattered) = scattered {
+            return transmittance * spectral_radiance(scattered, world, lights, background, depth - 1, None);
+        }
+        let direct: f64 = light_samples(&r, &rec, world, lights)
+            .map(|(f, li)| spectrum::rgb_to_spectrum(f, lambda) * spectrum::rgb_to_spectrum(li, lambda))
+            .sum();
+        let material = rec.mat_ptr();
+        let direct = transmittance * (spectrum::rgb_to_spectrum(material.emitted(&rec), lambda) + direct);
+        let (attenuation, scattered, flag) = material.scatter(&r, &rec);
+        if let Some(features) = features {
+            features.albedo = attenuation;
+        }
+        if flag {
+            let scattered = scattered.with_wavelength(r.wavelength());
+            return direct + transmittance * spectrum::rgb_to_spectrum(attenuation, lambda) * spectral_radiance(scattered, world, lights, background, depth - 1, None);
+        }
+        return direct;
+    }
+    if let Some(features) = features {
+        *features = PrimaryFeatures::missed(&r, background);
+    }
+    spectrum::rgb_to_spectrum(background.color(&r), lambda)
+}
+
+// Traces r at one randomly drawn wavelength and returns its RGB contribution
+pub fn spectral_ray_color(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32) -> Color {
+    spectral_sample(r, world, lights, background, depth, None)
+}
+
+fn spectral_sample(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32,
+    features: Option<&mut PrimaryFeatures>) -> Color {
+    let mut rng = random::rng();
+    let lambda = spectrum::sample_wavelength(rng.gen_range(0.0, 1.0));
+    let radiance = spectral_radiance(r.with_wavelength(Some(lambda)), world, lights, background, depth, features);
+    spectrum::spectrum_to_rgb(radiance, lambda)
+}
+
+pub fn sample_color(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32, mode: ColorMode) -> Color {
+    match mode {
+        ColorMode::Rgb => ray_color(r, world, lights, background, depth),
+        ColorMode::Spectral => spectral_ray_color(r, world, lights, background, depth)
+    }
+}
+
+// sample_color that also reports what r hit first. The color is the same one
+// sample_color gives for the same random numbers.
+pub fn sample_color_with_features(r: Ray, world: &dyn Hittable, lights: &[Rc<dyn Light>], background: &Background, depth: i32,
+    mode: ColorMode) -> (Color, PrimaryFeatures) {
+    let mut features = PrimaryFeatures::missed(&r, background);
+    let color = match mode {
+        ColorMode::Rgb => trace(r, world, lights, background, depth, Color::new((1.0, 1.0, 1.0)), Some(&mut features)),
+        ColorMode::Spectral => spectral_sample(r, world, lights, background, depth, Some(&mut features))
+    };
+    (color, features)
+}
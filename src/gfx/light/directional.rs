@@ -0,0 +1,30 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::light::Light;
+
+// Light from infinitely far away such as the sun, irradiance is measured
+// on a surface facing the light
+pub struct DirectionalLight {
+    to_light: Vec3,
+    irradiance: Color
+}
+
+pub trait DirectionalLightFn : Light {
+    // direction is the way the light travels
+    fn new(direction: Vec3, irradiance: Color) -> Self;
+}
+
+impl DirectionalLightFn for DirectionalLight {
+    fn new(direction: Vec3, irradiance: Color) -> Self {
+        DirectionalLight { to_light: -direction.unitize(), irradiance }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _p: Point3) -> (Color, Vec3, f64) {
+        (self.irradiance, self.to_light, f64::INFINITY)
+    }
+}
@@ -0,0 +1,15 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+
+// Analytic lights that cannot be hit by rays and are only reached by sampling
+// them directly from a shaded point
+pub trait Light {
+    // Light arriving at p, the unit direction from p towards the light and the
+    // distance to it, which is infinite for lights at infinity
+    fn sample_li(&self, p: Point3) -> (Color, Vec3, f64);
+}
+
+pub mod point;
+pub mod spot;
+pub mod directional;
@@ -0,0 +1,31 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::light::Light;
+
+// Isotropic point emitter, intensity is power per unit solid angle
+pub struct PointLight {
+    position: Point3,
+    intensity: Color
+}
+
+pub trait PointLightFn : Light {
+    fn new(position: Point3, intensity: Color) -> Self;
+}
+
+impl PointLightFn for PointLight {
+    fn new(position: Point3, intensity: Color) -> Self {
+        PointLight { position, intensity }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: Point3) -> (Color, Vec3, f64) {
+        let to_light = self.position - p;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        (self.intensity / distance_squared, to_light / distance, distance)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::degrees_to_radians;
+
+use crate::gfx::light::Light;
+
+// Point emitter restricted to a cone around the direction it points in
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_cone: f64,
+    cos_falloff_start: f64
+}
+
+pub trait SpotLightFn : Light {
+    // cone_angle is the half angle in degrees where the light ends and
+    // falloff_start the half angle where it starts fading out
+    fn new(position: Point3, target: Point3, intensity: Color, cone_angle: f64, falloff_start: f64) -> Self;
+}
+
+impl SpotLightFn for SpotLight {
+    fn new(position: Point3, target: Point3, intensity: Color, cone_angle: f64, falloff_start: f64) -> Self {
+        let direction = (target - position).unitize();
+        let cos_cone = degrees_to_radians(cone_angle).cos();
+        let cos_falloff_start = degrees_to_radians(falloff_start.min(cone_angle)).cos();
+        SpotLight { position, direction, intensity, cos_cone, cos_falloff_start }
+    }
+}
+
+impl SpotLight {
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_cone {
+            return 0.0;
+        }
+        let t = (cos_theta - self.cos_cone) / (self.cos_falloff_start - self.cos_cone);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: Point3) -> (Color, Vec3, f64) {
+        let to_light = self.position - p;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        let wi = to_light / distance;
+        let falloff = self.falloff(-wi.dot(self.direction));
+        (falloff * self.intensity / distance_squared, wi, distance)
+    }
+}
@@ -0,0 +1,121 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::schlick;
+use crate::gfx::material::fresnel_dielectric;
+use crate::gfx::material::beer_lambert;
+use crate::gfx::material::absorption_from_tint;
+
+const MAX_INTERNAL_BOUNCES: i32 = 16;
+
+// Smooth dielectric clearcoat over any base material, such as varnish or lacquer.
+// The coat is treated as infinitely thin, rays refract through it on the way
+// to the base and back out again.
+pub struct CoatedMaterial {
+    base: Rc<dyn Material>,
+    ior: f64,
+    absorption: Color
+}
+
+pub trait CoatedMaterialFn : Material {
+    fn new(base: Rc<dyn Material>, ior: f64) -> Self;
+    // tint is the color the coat transmits for one pass at normal incidence
+    fn new_tinted(base: Rc<dyn Material>, ior: f64, tint: Color) -> Self;
+}
+
+impl CoatedMaterialFn for CoatedMaterial {
+    fn new(base: Rc<dyn Material>, ior: f64) -> Self {
+        CoatedMaterial { base, ior, absorption: Color::new((0.0, 0.0, 0.0)) }
+    }
+
+    fn new_tinted(base: Rc<dyn Material>, ior: f64, tint: Color) -> Self {
+        CoatedMaterial { base, ior, absorption: absorption_from_tint(tint, 1.0) }
+    }
+}
+
+impl Material for CoatedMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let unit_direction = r_in.direction().unitize();
+        let cos_in = (-unit_direction.dot(rec.normal())).min(1.0);
+
+        let mut rng = random::rng();
+        if rng.gen_range(0.0, 1.0) < schlick(cos_in, self.ior) {
+            let reflected = unit_direction.reflect(rec.normal());
+            return (Color::new((1.0, 1.0, 1.0)), Ray::new(rec.p(), reflected), true);
+        }
+
+        // the base sees light refracted into the coat, light reflected back
+        // down by the underside of the coat gets another bounce off the base
+        let mut incoming = unit_direction.refract(rec.normal(), 1.0 / self.ior);
+        let mut throughput = Color::new((1.0, 1.0, 1.0));
+        for _ in 0..MAX_INTERNAL_BOUNCES {
+            let (attenuation, scattered, flag) = self.base.scatter(&Ray::new(r_in.origin(), incoming), rec);
+            if !flag {
+                return (attenuation, scattered, false);
+            }
+            throughput = throughput * attenuation;
+            let out = scattered.direction().unitize();
+            let cos_out = out.dot(rec.normal());
+            if cos_out <= 0.0 {
+                // transmitted into the base object, the coat only added its interface
+                return (throughput, scattered, true);
+            }
+
+            let path = 1.0 / (-incoming.dot(rec.normal())).max(1e-4) + 1.0 / cos_out;
+            throughput = throughput * beer_lambert(self.absorption, path);
+            if rng.gen_range(0.0, 1.0) < fresnel_dielectric(cos_out, 1.0 / self.ior) {
+                incoming = out.reflect(rec.normal());
+                continue;
+            }
+            let exit = out.refract(-rec.normal(), self.ior);
+            return (throughput, Ray::new(scattered.origin(), exit), true);
+        }
+        (Color::new((0.0, 0.0, 0.0)), *r_in, false)
+    }
+
+    // Single scattering off the base through the coat (Weidlich and Wilkie 2007),
+    // the light refracted back down by the coat is not accounted for here
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let unit_direction = r_in.direction().unitize();
+        let cos_o = -unit_direction.dot(rec.normal());
+        let cos_i = wi.dot(rec.normal());
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let incoming = unit_direction.refract(rec.normal(), 1.0 / self.ior);
+        let wi_inside = -(-wi).refract(rec.normal(), 1.0 / self.ior);
+        let cos_i_inside = wi_inside.dot(rec.normal());
+        let base = self.base.eval(&Ray::new(r_in.origin(), incoming), rec, wi_inside);
+
+        let transmit = (1.0 - schlick(cos_o, self.ior)) * (1.0 - schlick(cos_i, self.ior));
+        let path = 1.0 / (-incoming.dot(rec.normal())).max(1e-4) + 1.0 / cos_i_inside;
+        // refraction squeezes the solid angle by cos_i / (ior^2 cos_i_inside)
+        let jacobian = cos_i / (self.ior * self.ior * cos_i_inside);
+        (transmit * jacobian) * beer_lambert(self.absorption, path) * base
+    }
+
+    // Density of leaving through the coat after a single bounce off the base
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let unit_direction = r_in.direction().unitize();
+        let cos_o = -unit_direction.dot(rec.normal());
+        if cos_o <= 0.0 || wi.dot(rec.normal()) <= 0.0 {
+            return 0.0;
+        }
+        let incoming = unit_direction.refract(rec.normal(), 1.0 / self.ior);
+        let wi_inside = -(-wi).refract(rec.normal(), 1.0 / self.ior);
+        let cos_i_inside = wi_inside.dot(rec.normal());
+        let jacobian = wi.dot(rec.normal()) / (self.ior * self.ior * cos_i_inside);
+        (1.0 - schlick(cos_o, self.ior)) * jacobian * self.base.pdf(&Ray::new(r_in.origin(), incoming), rec, wi_inside)
+    }
+}
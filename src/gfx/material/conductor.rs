@@ -0,0 +1,122 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_conductor;
+use crate::gfx::material::microfacet::Ggx;
+use crate::gfx::material::microfacet::GgxTraits;
+
+// Rough metal with GGX microfacets and a measured complex index of refraction
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: Ggx
+}
+
+pub trait ConductorFn : Material {
+    fn new(eta: Color, k: Color, roughness: f64) -> Self;
+    fn new_anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self;
+    // Metal of a given color, seen head on it reflects exactly that color
+    fn from_reflectance(reflectance: Color, roughness: f64) -> Self;
+
+    // RGB fits of measured spectral data
+    fn gold(roughness: f64) -> Self;
+    fn copper(roughness: f64) -> Self;
+    fn silver(roughness: f64) -> Self;
+    fn aluminum(roughness: f64) -> Self;
+}
+
+impl ConductorFn for Conductor {
+    fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Conductor::new_anisotropic(eta, k, roughness, roughness)
+    }
+
+    fn new_anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        let distribution = Ggx::from_roughness(roughness_u, roughness_v);
+        Conductor { eta, k, distribution }
+    }
+
+    fn from_reflectance(reflectance: Color, roughness: f64) -> Self {
+        // with eta = 1 the reflectance at normal incidence is k^2 / (4 + k^2)
+        let k = |f0: f64| {
+            let f0 = f0.clamp(0.0, 0.999);
+            2.0 * (f0 / (1.0 - f0)).sqrt()
+        };
+        Conductor::new(Color::new((1.0, 1.0, 1.0)), Color::new((k(reflectance.x()), k(reflectance.y()), k(reflectance.z()))), roughness)
+    }
+
+    fn gold(roughness: f64) -> Self {
+        Conductor::new(Color::new((0.143, 0.374, 1.442)), Color::new((3.983, 2.385, 1.603)), roughness)
+    }
+
+    fn copper(roughness: f64) -> Self {
+        Conductor::new(Color::new((0.200, 0.924, 1.102)), Color::new((3.912, 2.452, 2.142)), roughness)
+    }
+
+    fn silver(roughness: f64) -> Self {
+        Conductor::new(Color::new((0.155, 0.117, 0.138)), Color::new((4.828, 3.122, 2.147)), roughness)
+    }
+
+    fn aluminum(roughness: f64) -> Self {
+        Conductor::new(Color::new((1.657, 0.880, 0.521)), Color::new((9.224, 6.270, 4.837)), roughness)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        if wo.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        let mut rng = random::rng();
+        let wm = self.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+        let wi = (-wo).reflect(wm);
+        if wi.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        // f * cos / pdf for visible normal sampling reduces to F * G2 / G1
+        let fresnel = fresnel_conductor(wo.dot(wm), self.eta, self.k);
+        let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        let scattered = Ray::new(rec.p(), uvw.local(wi));
+        (weight * fresnel, scattered, true)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let wm = (wo + wi).unitize();
+        let fresnel = fresnel_conductor(wo.dot(wm), self.eta, self.k);
+        // F D G / (4 cos_o cos_i), times cos_i
+        let value = self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z());
+        value * fresnel
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unitize();
+        self.distribution.pdf(wo, wm) / (4.0 * wo.dot(wm))
+    }
+}
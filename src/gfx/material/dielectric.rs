@@ -1,52 +1,133 @@
-use rand::Rng;
-use crate::gfx::material::Material;
-use crate::gfx::material::schlick;
-
-use crate::gfx::hittable::HitRecord;
-use crate::gfx::hittable::HitRecordTraits;
-
-use crate::basic_types::vec3::Color;
-use crate::basic_types::ray::Ray;
-use crate::basic_types::ray::RayTraits;
-use crate::basic_types::vec3::Vec3Traits;
-
-pub struct Dielectric {
-    ref_idx: f64
-}
-
-pub trait DielectricFn : Material {
-    fn new(ref_idx: f64) -> Self;
-}
-
-impl DielectricFn for Dielectric {
-    fn new(ref_idx: f64) -> Self {
-        Dielectric{ref_idx}
-    }
-}
-
-impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
-        let attenuation = Color::new((1.0, 1.0, 1.0));
-        let etai_over_etat = if rec.front() {1.0 / self.ref_idx} else {self.ref_idx};
-
-        let unit_direction = r_in.direction().unitize();
-        let val = -unit_direction.dot(rec.normal());
-        let cos_theta = if val < 1.0 {val} else {1.0};
-        let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
-        if etai_over_etat * sin_theta > 1.0 {
-            let reflected = unit_direction.reflect(rec.normal());
-            let scattered = Ray::new(rec.p(), reflected);
-            return (attenuation, scattered, true);
-        }
-        let reflect_prob = schlick(cos_theta, etai_over_etat);
-        let mut rng = rand::thread_rng()    ;
-        if rng.gen_range(0.0, 1.0) < reflect_prob {
-            let reflected = unit_direction.reflect(rec.normal());
-            let scattered = Ray::new(rec.p(), reflected);
-            return (attenuation, scattered, true);
-        }
-        let refracted = unit_direction.refract(rec.normal(), etai_over_etat);
-        let scattered = Ray::new(rec.p(), refracted);
-        (attenuation, scattered, true)
-    }
+use rand::Rng;
+use crate::basic_types::random;
+use crate::gfx::material::Material;
+use crate::gfx::material::schlick;
+use crate::gfx::material::beer_lambert;
+use crate::gfx::material::absorption_from_tint;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::spectrum::LAMBDA_D;
+
+// Wavelength dependent IOR, coefficients use wavelengths in micrometers
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dispersion {
+    // n = a + b / lambda^2
+    Cauchy(f64, f64),
+    // n^2 = 1 + sum b_i lambda^2 / (lambda^2 - c_i)
+    Sellmeier([f64; 3], [f64; 3])
+}
+
+pub trait DispersionTraits {
+    fn ior(&self, lambda_nm: f64) -> f64;
+}
+
+impl DispersionTraits for Dispersion {
+    fn ior(&self, lambda_nm: f64) -> f64 {
+        let l2 = (lambda_nm * 1e-3) * (lambda_nm * 1e-3);
+        match self {
+            Dispersion::Cauchy(a, b) => a + b / l2,
+            Dispersion::Sellmeier(b, c) => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>();
+                n2.sqrt()
+            }
+        }
+    }
+}
+
+pub struct Dielectric {
+    ref_idx: f64,
+    absorption: Color,
+    dispersion: Option<Dispersion>
+}
+
+pub trait DielectricFn : Material {
+    fn new(ref_idx: f64) -> Self;
+    // Tinted glass that transmits tint after tint_distance world units inside
+    // it, which has to be positive
+    fn new_tinted(ref_idx: f64, tint: Color, tint_distance: f64) -> Self;
+    // Glass whose IOR varies with the ray's wavelength in spectral mode,
+    // RGB rays see the IOR at the d-line
+    fn new_dispersive(dispersion: Dispersion) -> Self;
+    fn crown_glass() -> Self;
+    fn flint_glass() -> Self;
+    fn diamond() -> Self;
+}
+
+impl DielectricFn for Dielectric {
+    fn new(ref_idx: f64) -> Self {
+        Dielectric{ref_idx, absorption: Color::new((0.0, 0.0, 0.0)), dispersion: None}
+    }
+
+    fn new_tinted(ref_idx: f64, tint: Color, tint_distance: f64) -> Self {
+        Dielectric{ref_idx, absorption: absorption_from_tint(tint, tint_distance), dispersion: None}
+    }
+
+    fn new_dispersive(dispersion: Dispersion) -> Self {
+        Dielectric{ref_idx: dispersion.ior(LAMBDA_D), absorption: Color::new((0.0, 0.0, 0.0)), dispersion: Some(dispersion)}
+    }
+
+    // Schott N-BK7
+    fn crown_glass() -> Self {
+        Dielectric::new_dispersive(Dispersion::Sellmeier([1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653]))
+    }
+
+    // Schott N-SF11
+    fn flint_glass() -> Self {
+        Dielectric::new_dispersive(Dispersion::Sellmeier([1.73759695, 0.313747346, 1.89878101],
+            [0.013188707, 0.0623068142, 155.23629]))
+    }
+
+    fn diamond() -> Self {
+        Dielectric::new_dispersive(Dispersion::Sellmeier([4.3356, 0.3306, 0.0],
+            [0.011236, 0.030625, 0.0]))
+    }
+}
+
+impl Dielectric {
+    fn ior(&self, r_in: &Ray) -> f64 {
+        match (self.dispersion, r_in.wavelength()) {
+            (Some(dispersion), Some(lambda)) => dispersion.ior(lambda),
+            _ => self.ref_idx
+        }
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        // hitting a back face means the ray travelled inside the object to get here
+        let attenuation = if rec.front() {
+            Color::new((1.0, 1.0, 1.0))
+        } else {
+            beer_lambert(self.absorption, rec.t() * r_in.direction().length())
+        };
+        let ref_idx = self.ior(r_in);
+        let etai_over_etat = if rec.front() {1.0 / ref_idx} else {ref_idx};
+
+        let unit_direction = r_in.direction().unitize();
+        let val = -unit_direction.dot(rec.normal());
+        let cos_theta = if val < 1.0 {val} else {1.0};
+        let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
+        if etai_over_etat * sin_theta > 1.0 {
+            let reflected = unit_direction.reflect(rec.normal());
+            let scattered = Ray::new(rec.p(), reflected);
+            return (attenuation, scattered, true);
+        }
+        let reflect_prob = schlick(cos_theta, etai_over_etat);
+        let mut rng = random::rng()    ;
+        if rng.gen_range(0.0, 1.0) < reflect_prob {
+            let reflected = unit_direction.reflect(rec.normal());
+            let scattered = Ray::new(rec.p(), reflected);
+            return (attenuation, scattered, true);
+        }
+        let refracted = unit_direction.refract(rec.normal(), etai_over_etat);
+        let scattered = Ray::new(rec.p(), refracted);
+        (attenuation, scattered, true)
+    }
 }
\ No newline at end of file
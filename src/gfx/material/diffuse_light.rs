@@ -0,0 +1,48 @@
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::texture::Texture;
+use crate::gfx::texture::solid_color::SolidColor;
+use crate::gfx::texture::solid_color::SolidColorFn;
+
+// Area light that emits evenly from the front of any surface it is put on and
+// absorbs everything arriving at it. Lights are only reached by paths bouncing
+// into them, so small bright ones are noisy.
+pub struct DiffuseLight {
+    emit: Rc<dyn Texture>
+}
+
+pub trait DiffuseLightFn : Material {
+    fn new(emit: Color) -> Self;
+    fn new_textured(emit: Rc<dyn Texture>) -> Self;
+}
+
+impl DiffuseLightFn for DiffuseLight {
+    fn new(emit: Color) -> Self {
+        DiffuseLight { emit: Rc::new(SolidColor::new(emit)) }
+    }
+
+    fn new_textured(emit: Rc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, r_in: &Ray, _rec: &HitRecord) -> (Color, Ray, bool) {
+        (Color::new((0.0, 0.0, 0.0)), *r_in, false)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        if !rec.front() {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        self.emit.value(rec.u(), rec.v(), rec.p())
+    }
+}
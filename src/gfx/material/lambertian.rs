@@ -1,35 +1,56 @@
-use crate::gfx::material::Material;
-
-use crate::gfx::hittable::HitRecord;
-use crate::gfx::hittable::HitRecordTraits;
-
-
-use crate::basic_types::vec3::Color;
-use crate::basic_types::vec3::Vec3Traits;
-
-use crate::basic_types::ray::Ray;
-use crate::basic_types::ray::RayTraits;
-
-pub struct Lambertian {
-    albedo: Color
-}
-
-pub trait LambertianFn : Material {
-    fn new(albedo: Color) -> Lambertian;
-}
-
-
-impl LambertianFn for Lambertian {
-    fn new(albedo: Color) -> Lambertian {
-        Lambertian{albedo}
-    }
-}
-
-impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
-        let scatter_direction = rec.normal() + Color::random_unit_vector();
-        let scattered = Ray::new(rec.p(), scatter_direction);
-        let attenuation = self.albedo;
-        return (attenuation, scattered, true);
-    }
+use std::rc::Rc;
+
+use crate::gfx::material::Material;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::texture::Texture;
+use crate::gfx::texture::solid_color::SolidColor;
+use crate::gfx::texture::solid_color::SolidColorFn;
+
+pub struct Lambertian {
+    albedo: Rc<dyn Texture>
+}
+
+pub trait LambertianFn : Material {
+    fn new(albedo: Color) -> Self;
+    fn new_textured(albedo: Rc<dyn Texture>) -> Self;
+}
+
+
+impl LambertianFn for Lambertian {
+    fn new(albedo: Color) -> Lambertian {
+        Lambertian{albedo: Rc::new(SolidColor::new(albedo))}
+    }
+
+    fn new_textured(albedo: Rc<dyn Texture>) -> Lambertian {
+        Lambertian{albedo}
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let scatter_direction = rec.normal() + Color::random_unit_vector();
+        let scattered = Ray::new(rec.p(), scatter_direction);
+        let attenuation = self.albedo.value(rec.u(), rec.v(), rec.p());
+        (attenuation, scattered, true)
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let cosine = wi.dot(rec.normal()).max(0.0);
+        (cosine / std::f64::consts::PI) * self.albedo.value(rec.u(), rec.v(), rec.p())
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        wi.dot(rec.normal()).max(0.0) / std::f64::consts::PI
+    }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+// Smallest alpha we trace with, below this the lobe is numerically a mirror
+const MIN_ALPHA: f64 = 1e-4;
+
+// GGX / Trowbridge-Reitz distribution with Smith masking.
+// All directions are in the local shading frame where z is the normal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ggx {
+    alpha_x: f64,
+    alpha_y: f64
+}
+
+pub trait GgxTraits {
+    fn new(alpha_x: f64, alpha_y: f64) -> Self;
+    fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self;
+
+    fn alpha_x(&self) -> f64;
+    fn alpha_y(&self) -> f64;
+
+    fn d(&self, wm: Vec3) -> f64;
+    fn lambda(&self, w: Vec3) -> f64;
+    fn g1(&self, w: Vec3) -> f64;
+    fn g(&self, wo: Vec3, wi: Vec3) -> f64;
+    // Density of visible normals as seen from wo
+    fn pdf(&self, wo: Vec3, wm: Vec3) -> f64;
+    fn sample_visible_normal(&self, wo: Vec3, u1: f64, u2: f64) -> Vec3;
+}
+
+impl GgxTraits for Ggx {
+    fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Ggx { alpha_x: alpha_x.max(MIN_ALPHA), alpha_y: alpha_y.max(MIN_ALPHA) }
+    }
+
+    // Perceptually linear roughness, squared as artists expect
+    fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self {
+        Ggx::new(roughness_u * roughness_u, roughness_v * roughness_v)
+    }
+
+    fn alpha_x(&self) -> f64 {
+        self.alpha_x
+    }
+
+    fn alpha_y(&self) -> f64 {
+        self.alpha_y
+    }
+
+    fn d(&self, wm: Vec3) -> f64 {
+        if wm.z() <= 0.0 {
+            return 0.0;
+        }
+        let x = wm.x() / self.alpha_x;
+        let y = wm.y() / self.alpha_y;
+        let e = x * x + y * y + wm.z() * wm.z();
+        1.0 / (std::f64::consts::PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: Vec3) -> f64 {
+        let z2 = w.z() * w.z();
+        if z2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let a2 = self.alpha_x * self.alpha_x * w.x() * w.x() + self.alpha_y * self.alpha_y * w.y() * w.y();
+        0.5 * (-1.0 + (1.0 + a2 / z2).sqrt())
+    }
+
+    fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    fn pdf(&self, wo: Vec3, wm: Vec3) -> f64 {
+        if wo.z() == 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * wo.dot(wm).max(0.0) * self.d(wm) / wo.z().abs()
+    }
+
+    // Heitz 2018, "Sampling the GGX Distribution of Visible Normals"
+    fn sample_visible_normal(&self, wo: Vec3, u1: f64, u2: f64) -> Vec3 {
+        let vh = Vec3::new((self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z())).unitize();
+        let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if lensq > 0.0 {
+            Vec3::new((-vh.y(), vh.x(), 0.0)) / lensq.sqrt()
+        } else {
+            Vec3::new((1.0, 0.0, 0.0))
+        };
+        let t2 = vh.cross(t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+        Vec3::new((self.alpha_x * nh.x(), self.alpha_y * nh.y(), nh.z().max(0.0))).unitize()
+    }
+}
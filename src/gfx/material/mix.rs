@@ -0,0 +1,58 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::ray::Ray;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::texture::Texture;
+use crate::gfx::texture::solid_color::SolidColor;
+use crate::gfx::texture::solid_color::SolidColorFn;
+
+// Stochastic blend of two materials, a mask of 0 is all `a` and 1 is all `b`
+pub struct MixMaterial {
+    a: Rc<dyn Material>,
+    b: Rc<dyn Material>,
+    mask: Rc<dyn Texture>
+}
+
+pub trait MixMaterialFn : Material {
+    fn new(a: Rc<dyn Material>, b: Rc<dyn Material>, amount: f64) -> Self;
+    fn new_masked(a: Rc<dyn Material>, b: Rc<dyn Material>, mask: Rc<dyn Texture>) -> Self;
+}
+
+impl MixMaterialFn for MixMaterial {
+    fn new(a: Rc<dyn Material>, b: Rc<dyn Material>, amount: f64) -> Self {
+        MixMaterial { a, b, mask: Rc::new(SolidColor::from_scalar(amount)) }
+    }
+
+    fn new_masked(a: Rc<dyn Material>, b: Rc<dyn Material>, mask: Rc<dyn Texture>) -> Self {
+        MixMaterial { a, b, mask }
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let amount = self.mask.scalar(rec.u(), rec.v(), rec.p());
+        let mut rng = random::rng();
+        if rng.gen_range(0.0, 1.0) < amount {
+            self.b.scatter(r_in, rec)
+        } else {
+            self.a.scatter(r_in, rec)
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let amount = self.mask.scalar(rec.u(), rec.v(), rec.p());
+        (1.0 - amount) * self.a.eval(r_in, rec, wi) + amount * self.b.eval(r_in, rec, wi)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let amount = self.mask.scalar(rec.u(), rec.v(), rec.p());
+        (1.0 - amount) * self.a.pdf(r_in, rec, wi) + amount * self.b.pdf(r_in, rec, wi)
+    }
+}
@@ -1,19 +1,138 @@
-use crate::basic_types::vec3::Color;
-use crate::basic_types::ray::Ray;
-use crate::gfx::hittable::HitRecord;
-
-fn schlick(cosine: f64, ref_idx: f64) -> f64 {
-    let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
-    r0 *= r0;
-    return r0 + (1.0 - r0) * (1.0 - cosine).powi(5);
-}
-
-pub trait Material {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool);
-}
-
-
-
-pub mod lambertian;
-pub mod metal;
-pub mod dielectric;
\ No newline at end of file
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::ray::Ray;
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::medium::Medium;
+use num::complex::Complex64;
+
+fn schlick(cosine: f64, ref_idx: f64) -> f64 {
+    let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
+    r0 *= r0;
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+}
+
+// Exact unpolarized Fresnel reflectance, eta is transmitted over incident IOR
+fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parl * r_parl + r_perp * r_perp)
+}
+
+// Beer-Lambert transmittance after travelling distance through an absorbing medium
+fn beer_lambert(absorption: Color, distance: f64) -> Color {
+    Color::new(((-absorption.x() * distance).exp(),
+        (-absorption.y() * distance).exp(),
+        (-absorption.z() * distance).exp()))
+}
+
+// Absorption coefficient that leaves tint after travelling distance. A
+// distance of zero would make a clear channel 0/0 and poison every path with NaN.
+fn absorption_from_tint(tint: Color, distance: f64) -> Color {
+    assert!(distance > 0.0, "tint distance must be positive, got {}", distance);
+    Color::new((-tint.x().max(1e-6).ln() / distance,
+        -tint.y().max(1e-6).ln() / distance,
+        -tint.z().max(1e-6).ln() / distance))
+}
+
+// Unpolarized Fresnel reflectance of a conductor with complex IOR eta + i*k
+fn fresnel_conductor_channel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta * cos_theta;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+    let t1 = a2b2 + cos2;
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rs + rp)
+}
+
+fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    Color::new((fresnel_conductor_channel(cos_theta, eta.x(), k.x()),
+        fresnel_conductor_channel(cos_theta, eta.y(), k.y()),
+        fresnel_conductor_channel(cos_theta, eta.z(), k.z())))
+}
+
+// Airy reflectance at wavelength lambda of a film with thickness in nanometers,
+// lying between an incident medium eta_i and a substrate with complex IOR eta_t + i*k_t
+fn fresnel_thin_film(cos_theta_i: f64, lambda: f64, thickness: f64, eta_i: f64, film_ior: f64, eta_t: f64, k_t: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let one = Complex64::new(1.0, 0.0);
+    let n1 = Complex64::new(eta_i, 0.0);
+    let n2 = Complex64::new(film_ior, 0.0);
+    let n3 = Complex64::new(eta_t, k_t);
+    // Snell's law with complex cosines, so total internal reflection falls out
+    let sin2_i = Complex64::new(1.0 - cos_i * cos_i, 0.0);
+    let cos1 = Complex64::new(cos_i, 0.0);
+    let cos2 = (one - n1 * n1 * sin2_i / (n2 * n2)).sqrt();
+    let cos3 = (one - n1 * n1 * sin2_i / (n3 * n3)).sqrt();
+
+    // phase between the waves reflected off the top and bottom of the film
+    let phase = Complex64::new(0.0, 4.0 * std::f64::consts::PI * thickness / lambda) * n2 * cos2;
+    let delay = phase.exp();
+    let airy = |r12: Complex64, r23: Complex64| {
+        ((r12 + r23 * delay) / (one + r12 * r23 * delay)).norm_sqr()
+    };
+    let rs = |na: Complex64, ca: Complex64, nb: Complex64, cb: Complex64| (na * ca - nb * cb) / (na * ca + nb * cb);
+    let rp = |na: Complex64, ca: Complex64, nb: Complex64, cb: Complex64| (nb * ca - na * cb) / (nb * ca + na * cb);
+
+    let r_s = airy(rs(n1, cos1, n2, cos2), rs(n2, cos2, n3, cos3));
+    let r_p = airy(rp(n1, cos1, n2, cos2), rp(n2, cos2, n3, cos3));
+    (0.5 * (r_s + r_p)).clamp(0.0, 1.0)
+}
+
+pub trait Material {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool);
+
+    // Scattering function times the cosine at the surface, for light arriving
+    // from unit direction wi and leaving back along r_in. Purely specular
+    // materials cannot be evaluated for a given direction and return black.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _wi: Vec3) -> Color {
+        Color::new((0.0, 0.0, 0.0))
+    }
+
+    // Solid angle density with which scatter picks wi
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _wi: Vec3) -> f64 {
+        0.0
+    }
+
+    // Participating medium filling the closed object this material bounds
+    fn medium(&self) -> Option<&dyn Medium> {
+        None
+    }
+
+    // Radiance the surface gives off by itself towards the viewer of rec
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new((0.0, 0.0, 0.0))
+    }
+}
+
+
+
+pub mod lambertian;
+pub mod metal;
+pub mod dielectric;
+pub mod microfacet;
+pub mod conductor;
+pub mod rough_dielectric;
+pub mod thin_dielectric;
+pub mod principled;
+pub mod mix;
+pub mod coated;
+pub mod oren_nayar;
+pub mod sheen;
+pub mod normal_mapped;
+pub mod thin_film;
+pub mod subsurface;
+pub mod diffuse_light;
\ No newline at end of file
@@ -0,0 +1,144 @@
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::medium::Medium;
+use crate::gfx::texture::Texture;
+
+// uv offset used for the finite differences of height maps
+const BUMP_DELTA: f64 = 1e-3;
+// smallest cosine allowed between the viewer and the shading normal
+const MIN_COS: f64 = 1e-3;
+
+enum Perturbation {
+    // tangent space normal encoded as 0.5 * n + 0.5
+    NormalMap(Rc<dyn Texture>),
+    // scalar height and its scale in world units
+    Bump(Rc<dyn Texture>, f64)
+}
+
+// Wraps any material and shades it with a normal perturbed by a texture
+pub struct NormalMapped {
+    inner: Rc<dyn Material>,
+    perturbation: Perturbation
+}
+
+pub trait NormalMappedFn : Material {
+    fn new_normal_map(inner: Rc<dyn Material>, normal_map: Rc<dyn Texture>) -> Self;
+    fn new_bump_map(inner: Rc<dyn Material>, height: Rc<dyn Texture>, scale: f64) -> Self;
+}
+
+impl NormalMappedFn for NormalMapped {
+    fn new_normal_map(inner: Rc<dyn Material>, normal_map: Rc<dyn Texture>) -> Self {
+        NormalMapped { inner, perturbation: Perturbation::NormalMap(normal_map) }
+    }
+
+    fn new_bump_map(inner: Rc<dyn Material>, height: Rc<dyn Texture>, scale: f64) -> Self {
+        NormalMapped { inner, perturbation: Perturbation::Bump(height, scale) }
+    }
+}
+
+impl NormalMapped {
+    // Perturbed normal on the outside of the surface
+    fn outward_shading_normal(&self, rec: &HitRecord) -> Vec3 {
+        let n = if rec.front() {rec.normal()} else {-rec.normal()};
+        let (u, v, p) = (rec.u(), rec.v(), rec.p());
+        let dpdu = rec.dpdu();
+        let dpdv = rec.dpdv();
+
+        match &self.perturbation {
+            Perturbation::NormalMap(map) => {
+                let c = map.value(u, v, p);
+                let local = Vec3::new((2.0 * c.x() - 1.0, 2.0 * c.y() - 1.0, 2.0 * c.z() - 1.0));
+                let tangent = dpdu - n.dot(dpdu) * n;
+                let (t, b) = if tangent.length_squared() > 0.0 {
+                    let t = tangent.unitize();
+                    let b = n.cross(t);
+                    (t, if b.dot(dpdv) < 0.0 {-b} else {b})
+                } else {
+                    let uvw = Onb::build_from_w(n);
+                    (uvw.u(), uvw.v())
+                };
+                let ns = local.x() * t + local.y() * b + local.z() * n;
+                if ns.length_squared() > 0.0 {ns.unitize()} else {n}
+            }
+            Perturbation::Bump(height, scale) => {
+                let h = height.scalar(u, v, p);
+                let h_u = height.scalar(u + BUMP_DELTA, v, p + BUMP_DELTA * dpdu);
+                let h_v = height.scalar(u, v + BUMP_DELTA, p + BUMP_DELTA * dpdv);
+                let dpdu = dpdu + (scale * (h_u - h) / BUMP_DELTA) * n;
+                let dpdv = dpdv + (scale * (h_v - h) / BUMP_DELTA) * n;
+                let ns = dpdu.cross(dpdv);
+                if ns.length_squared() == 0.0 {
+                    return n;
+                }
+                let ns = ns.unitize();
+                if ns.dot(n) < 0.0 {-ns} else {ns}
+            }
+        }
+    }
+
+    // Copy of rec shaded with the perturbed normal. A normal that would face
+    // away from the viewer is bent back until the viewer is just above it.
+    fn shade(&self, r_in: &Ray, rec: &HitRecord) -> HitRecord {
+        let ns = self.outward_shading_normal(rec);
+        let mut ns = if rec.front() {ns} else {-ns};
+        let wo = -r_in.direction().unitize();
+        let cos = wo.dot(ns);
+        if cos < MIN_COS {
+            ns = (ns + (MIN_COS - cos) * wo).unitize();
+        }
+        let mut shaded = rec.clone();
+        shaded.set_shading_normal(ns);
+        shaded
+    }
+
+    // directions on opposite sides of the geometric and shading surfaces would leak light
+    fn consistent(rec: &HitRecord, shaded: &HitRecord, w: Vec3) -> bool {
+        w.dot(rec.normal()) * w.dot(shaded.normal()) > 0.0
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let shaded = self.shade(r_in, rec);
+        let (attenuation, scattered, flag) = self.inner.scatter(r_in, &shaded);
+        if !flag || NormalMapped::consistent(rec, &shaded, scattered.direction()) {
+            return (attenuation, scattered, flag);
+        }
+        // mirror the direction back to the side of the geometric surface the material intended
+        let d = scattered.direction();
+        let mirrored = d - 2.0 * d.dot(rec.normal()) * rec.normal();
+        (attenuation, Ray::new(scattered.origin(), mirrored), true)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let shaded = self.shade(r_in, rec);
+        if !NormalMapped::consistent(rec, &shaded, wi) {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        self.inner.eval(r_in, &shaded, wi)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let shaded = self.shade(r_in, rec);
+        if !NormalMapped::consistent(rec, &shaded, wi) {
+            return 0.0;
+        }
+        self.inner.pdf(r_in, &shaded, wi)
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        self.inner.medium()
+    }
+}
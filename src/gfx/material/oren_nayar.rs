@@ -0,0 +1,81 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::degrees_to_radians;
+
+// Rough diffuse surface made of V-shaped Lambertian facets (Oren and Nayar 1994).
+// sigma is the standard deviation of the facet slope angle in degrees,
+// a sigma of 0 is plain Lambertian.
+pub struct OrenNayar {
+    albedo: Color,
+    a: f64,
+    b: f64
+}
+
+pub trait OrenNayarFn : Material {
+    fn new(albedo: Color, sigma: f64) -> Self;
+}
+
+impl OrenNayarFn for OrenNayar {
+    fn new(albedo: Color, sigma: f64) -> Self {
+        let sigma = degrees_to_radians(sigma);
+        let sigma2 = sigma * sigma;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+        OrenNayar { albedo, a, b }
+    }
+}
+
+impl OrenNayar {
+    // f * pi / albedo for local directions above the surface
+    fn reflectance(&self, wo: Vec3, wi: Vec3) -> f64 {
+        let sin_theta_i = (1.0 - wi.z() * wi.z()).max(0.0).sqrt();
+        let sin_theta_o = (1.0 - wo.z() * wo.z()).max(0.0).sqrt();
+
+        let max_cos = if sin_theta_i > 1e-4 && sin_theta_o > 1e-4 {
+            ((wi.x() * wo.x() + wi.y() * wo.y()) / (sin_theta_i * sin_theta_o)).max(0.0)
+        } else {
+            0.0
+        };
+        let (sin_alpha, tan_beta) = if wi.z() > wo.z() {
+            (sin_theta_o, sin_theta_i / wi.z())
+        } else {
+            (sin_theta_i, sin_theta_o / wo.z())
+        };
+        self.a + self.b * max_cos * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = Vec3::random_cosine_direction();
+        // cosine sampling cancels the cosine and the 1 / pi of the BRDF
+        let attenuation = self.reflectance(wo, wi) * self.albedo;
+        (attenuation, Ray::new(rec.p(), uvw.local(wi)), true)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        (self.reflectance(wo, wi) * wi.z() / std::f64::consts::PI) * self.albedo
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        wi.dot(rec.normal()).max(0.0) / std::f64::consts::PI
+    }
+}
@@ -0,0 +1,327 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::ColorTraits;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_dielectric;
+use crate::gfx::material::microfacet::Ggx;
+use crate::gfx::material::microfacet::GgxTraits;
+use crate::gfx::texture::Texture;
+use crate::gfx::texture::solid_color::SolidColor;
+use crate::gfx::texture::solid_color::SolidColorFn;
+
+const PI: f64 = std::f64::consts::PI;
+
+// Disney style uber material. Every parameter is a texture, scalar
+// parameters are read from the first channel.
+pub struct Principled {
+    base_color: Rc<dyn Texture>,
+    metallic: Rc<dyn Texture>,
+    roughness: Rc<dyn Texture>,
+    specular: Rc<dyn Texture>,
+    specular_tint: Rc<dyn Texture>,
+    sheen: Rc<dyn Texture>,
+    clearcoat: Rc<dyn Texture>,
+    clearcoat_gloss: Rc<dyn Texture>,
+    transmission: Rc<dyn Texture>,
+    ior: Rc<dyn Texture>
+}
+
+pub trait PrincipledFn : Material {
+    // Dielectric plastic look: roughness 0.5, specular 0.5, IOR 1.5, all other lobes off
+    fn new(base_color: Rc<dyn Texture>) -> Self;
+
+    fn set_base_color(&mut self, base_color: Rc<dyn Texture>);
+    fn set_metallic(&mut self, metallic: Rc<dyn Texture>);
+    fn set_roughness(&mut self, roughness: Rc<dyn Texture>);
+    fn set_specular(&mut self, specular: Rc<dyn Texture>);
+    fn set_specular_tint(&mut self, specular_tint: Rc<dyn Texture>);
+    fn set_sheen(&mut self, sheen: Rc<dyn Texture>);
+    fn set_clearcoat(&mut self, clearcoat: Rc<dyn Texture>);
+    fn set_clearcoat_gloss(&mut self, clearcoat_gloss: Rc<dyn Texture>);
+    fn set_transmission(&mut self, transmission: Rc<dyn Texture>);
+    fn set_ior(&mut self, ior: Rc<dyn Texture>);
+}
+
+impl PrincipledFn for Principled {
+    fn new(base_color: Rc<dyn Texture>) -> Self {
+        let constant = |value: f64| -> Rc<dyn Texture> {Rc::new(SolidColor::from_scalar(value))};
+        Principled {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            clearcoat_gloss: constant(1.0),
+            transmission: constant(0.0),
+            ior: constant(1.5)
+        }
+    }
+
+    fn set_base_color(&mut self, base_color: Rc<dyn Texture>) {
+        self.base_color = base_color
+    }
+
+    fn set_metallic(&mut self, metallic: Rc<dyn Texture>) {
+        self.metallic = metallic
+    }
+
+    fn set_roughness(&mut self, roughness: Rc<dyn Texture>) {
+        self.roughness = roughness
+    }
+
+    fn set_specular(&mut self, specular: Rc<dyn Texture>) {
+        self.specular = specular
+    }
+
+    fn set_specular_tint(&mut self, specular_tint: Rc<dyn Texture>) {
+        self.specular_tint = specular_tint
+    }
+
+    fn set_sheen(&mut self, sheen: Rc<dyn Texture>) {
+        self.sheen = sheen
+    }
+
+    fn set_clearcoat(&mut self, clearcoat: Rc<dyn Texture>) {
+        self.clearcoat = clearcoat
+    }
+
+    fn set_clearcoat_gloss(&mut self, clearcoat_gloss: Rc<dyn Texture>) {
+        self.clearcoat_gloss = clearcoat_gloss
+    }
+
+    fn set_transmission(&mut self, transmission: Rc<dyn Texture>) {
+        self.transmission = transmission
+    }
+
+    fn set_ior(&mut self, ior: Rc<dyn Texture>) {
+        self.ior = ior
+    }
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+    (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+// Berry distribution used by the clearcoat lobe
+pub fn gtr1(cos_h: f64, alpha: f64) -> f64 {
+    if alpha >= 1.0 {
+        return 1.0 / PI;
+    }
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos_h * cos_h))
+}
+
+// Half vector drawn with density gtr1(cos_h) * cos_h
+pub fn sample_gtr1(alpha: f64, u1: f64, u2: f64) -> Vec3 {
+    let a2 = alpha * alpha;
+    let cos_theta = ((1.0 - a2.powf(1.0 - u1)) / (1.0 - a2)).max(0.0).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new((sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+}
+
+// Parameters resolved at one shading point, directions in the local frame
+struct Lobes {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    sheen: f64,
+    clearcoat: f64,
+    clearcoat_alpha: f64,
+    transmission: f64,
+    ior: f64,
+    spec0: Color,
+    distribution: Ggx,
+    // selection probabilities of diffuse, specular, clearcoat and transmission
+    probabilities: [f64; 4]
+}
+
+impl Lobes {
+    fn new(m: &Principled, rec: &HitRecord) -> Lobes {
+        let (u, v, p) = (rec.u(), rec.v(), rec.p());
+        let base_color = m.base_color.value(u, v, p);
+        let metallic = m.metallic.scalar(u, v, p).clamp(0.0, 1.0);
+        let roughness = m.roughness.scalar(u, v, p).clamp(0.0, 1.0);
+        let specular = m.specular.scalar(u, v, p).max(0.0);
+        let specular_tint = m.specular_tint.scalar(u, v, p).clamp(0.0, 1.0);
+        let sheen = m.sheen.scalar(u, v, p).max(0.0);
+        let clearcoat = m.clearcoat.scalar(u, v, p).max(0.0);
+        let clearcoat_gloss = m.clearcoat_gloss.scalar(u, v, p).clamp(0.0, 1.0);
+        let transmission = m.transmission.scalar(u, v, p).clamp(0.0, 1.0);
+        let ior = m.ior.scalar(u, v, p).max(1.0);
+
+        let white = Color::new((1.0, 1.0, 1.0));
+        let tint = Lobes::tint(base_color);
+        let spec0 = lerp(specular * 0.08 * lerp(white, tint, specular_tint), base_color, metallic);
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let mut probabilities = [diffuse_weight, 1.0, 0.25 * clearcoat, (1.0 - metallic) * transmission];
+        let total: f64 = probabilities.iter().sum();
+        for p in probabilities.iter_mut() {
+            *p /= total;
+        }
+
+        Lobes {
+            base_color,
+            metallic,
+            roughness,
+            sheen,
+            clearcoat,
+            clearcoat_alpha: (1.0 - clearcoat_gloss) * 0.1 + clearcoat_gloss * 0.001,
+            transmission,
+            ior,
+            spec0,
+            distribution: Ggx::from_roughness(roughness, roughness),
+            probabilities
+        }
+    }
+
+    // hue and saturation of the base color with unit luminance
+    fn tint(base_color: Color) -> Color {
+        let lum = base_color.luminance();
+        if lum > 0.0 {base_color / lum} else {Color::new((1.0, 1.0, 1.0))}
+    }
+
+    // Sum of all reflection lobes for wo and wi above the surface
+    fn f(&self, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let white = Color::new((1.0, 1.0, 1.0));
+        let h = (wo + wi).unitize();
+        let cos_d = wi.dot(h);
+
+        let fl = schlick_weight(wi.z());
+        let fv = schlick_weight(wo.z());
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let diffuse = (fd / PI) * self.base_color;
+        let sheen = self.sheen * schlick_weight(cos_d) * lerp(white, Lobes::tint(self.base_color), 0.5);
+        // light reflected by the specular layer never reaches the base, taken on both
+        // sides so the lobe stays reciprocal
+        let spec0 = self.spec0.luminance().clamp(0.0, 1.0);
+        let layer = (1.0 - spec0) * (1.0 - fl) * (1.0 - spec0) * (1.0 - fv);
+        let diffuse_weight = (1.0 - self.metallic) * (1.0 - self.transmission) * layer;
+
+        let fresnel = self.spec0 + schlick_weight(cos_d) * (white - self.spec0);
+        let spec = self.distribution.d(h) * self.distribution.g(wo, wi) / (4.0 * wo.z() * wi.z());
+
+        let clearcoat_g = Ggx::new(0.25, 0.25).g(wo, wi);
+        let clearcoat_f = 0.04 + 0.96 * schlick_weight(cos_d);
+        let clearcoat = 0.25 * self.clearcoat * gtr1(h.z(), self.clearcoat_alpha) * clearcoat_f * clearcoat_g
+            / (4.0 * wo.z() * wi.z());
+
+        diffuse_weight * (diffuse + sheen) + spec * fresnel + clearcoat * white
+    }
+
+    // Density of generating wi through any of the reflection lobes
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = (wo + wi).unitize();
+        let diffuse = wi.z() / PI;
+        let spec = self.distribution.g1(wo) * self.distribution.d(h) / (4.0 * wo.z());
+        let clearcoat = gtr1(h.z(), self.clearcoat_alpha) * h.z() / (4.0 * wo.dot(h));
+        self.probabilities[0] * diffuse + self.probabilities[1] * spec + self.probabilities[2] * clearcoat
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let black = Color::new((0.0, 0.0, 0.0));
+        let lobes = Lobes::new(self, rec);
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        if wo.z() <= 0.0 {
+            return (black, *r_in, false);
+        }
+        let mut rng = random::rng();
+
+        // leaving a transmissive object is a plain rough dielectric interface
+        if !rec.front() && lobes.transmission > 0.0 {
+            let wm = lobes.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+            let eta = 1.0 / lobes.ior;
+            let reflected = rng.gen_range(0.0, 1.0) < fresnel_dielectric(wo.dot(wm), eta);
+            let wi = if reflected {(-wo).reflect(wm)} else {(-wo).refract(wm, 1.0 / eta)};
+            if reflected != (wi.z() > 0.0) {
+                return (black, *r_in, false);
+            }
+            let weight = lobes.distribution.g(wo, wi) / lobes.distribution.g1(wo);
+            return (Color::new((weight, weight, weight)), Ray::new(rec.p(), uvw.local(wi)), true);
+        }
+
+        let choice = rng.gen_range(0.0, 1.0);
+        let p = lobes.probabilities;
+        let wi = if choice < p[0] {
+            Vec3::random_cosine_direction()
+        } else if choice < p[0] + p[1] {
+            let wm = lobes.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+            (-wo).reflect(wm)
+        } else if choice < p[0] + p[1] + p[2] {
+            let wm = sample_gtr1(lobes.clearcoat_alpha, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+            (-wo).reflect(wm)
+        } else {
+            // refraction into the object, the specular lobe already covers its reflection
+            let wm = lobes.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+            let fresnel = fresnel_dielectric(wo.dot(wm), lobes.ior);
+            if fresnel >= 1.0 {
+                return (black, *r_in, false);
+            }
+            let wi = (-wo).refract(wm, 1.0 / lobes.ior);
+            if wi.z() >= 0.0 {
+                return (black, *r_in, false);
+            }
+            let lobe_weight = (1.0 - lobes.metallic) * lobes.transmission;
+            let weight = lobe_weight * (1.0 - fresnel) * lobes.distribution.g(wo, wi) / lobes.distribution.g1(wo) / p[3];
+            return (weight * lobes.base_color, Ray::new(rec.p(), uvw.local(wi)), true);
+        };
+
+        let pdf = lobes.pdf(wo, wi);
+        if wi.z() <= 0.0 || pdf <= 0.0 {
+            return (black, *r_in, false);
+        }
+        let attenuation = lobes.f(wo, wi) * (wi.z() / pdf);
+        (attenuation, Ray::new(rec.p(), uvw.local(wi)), true)
+    }
+
+    // Reflection lobes only, transmission is reached through scatter alone
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let lobes = Lobes::new(self, rec);
+        if !rec.front() && lobes.transmission > 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        lobes.f(wo, wi) * wi.z().max(0.0)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let lobes = Lobes::new(self, rec);
+        if !rec.front() && lobes.transmission > 0.0 {
+            return 0.0;
+        }
+        let uvw = Onb::build_from_w(rec.normal());
+        lobes.pdf(uvw.to_local(-r_in.direction().unitize()), uvw.to_local(wi))
+    }
+}
@@ -0,0 +1,112 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_dielectric;
+use crate::gfx::material::beer_lambert;
+use crate::gfx::material::absorption_from_tint;
+use crate::gfx::material::microfacet::Ggx;
+use crate::gfx::material::microfacet::GgxTraits;
+
+// Frosted glass, GGX microfacet reflection and transmission (Walter et al. 2007)
+pub struct RoughDielectric {
+    ref_idx: f64,
+    absorption: Color,
+    distribution: Ggx
+}
+
+pub trait RoughDielectricFn : Material {
+    fn new(ref_idx: f64, roughness: f64) -> Self;
+    // tint_distance has to be positive, as for Dielectric::new_tinted
+    fn new_tinted(ref_idx: f64, roughness: f64, tint: Color, tint_distance: f64) -> Self;
+}
+
+impl RoughDielectricFn for RoughDielectric {
+    fn new(ref_idx: f64, roughness: f64) -> Self {
+        let distribution = Ggx::from_roughness(roughness, roughness);
+        RoughDielectric { ref_idx, absorption: Color::new((0.0, 0.0, 0.0)), distribution }
+    }
+
+    fn new_tinted(ref_idx: f64, roughness: f64, tint: Color, tint_distance: f64) -> Self {
+        let distribution = Ggx::from_roughness(roughness, roughness);
+        RoughDielectric { ref_idx, absorption: absorption_from_tint(tint, tint_distance), distribution }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let attenuation = if rec.front() {
+            Color::new((1.0, 1.0, 1.0))
+        } else {
+            beer_lambert(self.absorption, rec.t() * r_in.direction().length())
+        };
+        let eta = if rec.front() {self.ref_idx} else {1.0 / self.ref_idx};
+
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        if wo.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        let mut rng = random::rng();
+        let wm = self.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+        let reflect_prob = fresnel_dielectric(wo.dot(wm), eta);
+
+        // choosing the lobe by Fresnel cancels F, leaving G2 / G1 for either path
+        let wi = if rng.gen_range(0.0, 1.0) < reflect_prob {
+            let wi = (-wo).reflect(wm);
+            if wi.z() <= 0.0 {
+                return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+            }
+            wi
+        } else {
+            let wi = (-wo).refract(wm, 1.0 / eta);
+            if wi.z() >= 0.0 {
+                return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+            }
+            wi
+        };
+
+        let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        let scattered = Ray::new(rec.p(), uvw.local(wi));
+        (weight * attenuation, scattered, true)
+    }
+
+    // Only the glossy reflection is evaluated, light is never sampled through the surface
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let eta = if rec.front() {self.ref_idx} else {1.0 / self.ref_idx};
+        let wm = (wo + wi).unitize();
+        let fresnel = fresnel_dielectric(wo.dot(wm), eta);
+        let value = fresnel * self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z());
+        Color::new((value, value, value))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let eta = if rec.front() {self.ref_idx} else {1.0 / self.ref_idx};
+        let wm = (wo + wi).unitize();
+        fresnel_dielectric(wo.dot(wm), eta) * self.distribution.pdf(wo, wm) / (4.0 * wo.dot(wm))
+    }
+}
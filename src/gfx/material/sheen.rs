@@ -0,0 +1,148 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::ColorTraits;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+
+const PI: f64 = std::f64::consts::PI;
+// Resolution of the sheen albedo table over the cosine of the viewing angle
+const ALBEDO_ENTRIES: usize = 32;
+const ALBEDO_SAMPLES: usize = 64;
+
+// Cloth and velvet: a diffuse base plus the "Charlie" sheen lobe of Estevez and
+// Kulla 2017 with Neubelt and Pettineo visibility. Fibres standing up from the
+// surface make the sheen strongest at grazing angles.
+pub struct Sheen {
+    albedo: Color,
+    sheen_color: Color,
+    roughness: f64,
+    // directional albedo of a white sheen lobe, the base only gets what it leaves
+    sheen_albedo: Vec<f64>
+}
+
+pub trait SheenFn : Material {
+    fn new(albedo: Color, sheen_color: Color, roughness: f64) -> Self;
+}
+
+impl SheenFn for Sheen {
+    fn new(albedo: Color, sheen_color: Color, roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.07, 1.0);
+        let sheen_albedo = (0..ALBEDO_ENTRIES)
+            .map(|i| Sheen::directional_albedo(roughness, (i as f64 + 0.5) / ALBEDO_ENTRIES as f64))
+            .collect();
+        Sheen { albedo, sheen_color, roughness, sheen_albedo }
+    }
+}
+
+impl Sheen {
+    fn lobe(roughness: f64, wo: Vec3, wi: Vec3) -> f64 {
+        let h = (wo + wi).unitize();
+        let sin_theta_h = (1.0 - h.z() * h.z()).max(0.0).sqrt();
+        let inv_r = 1.0 / roughness;
+        let d = (2.0 + inv_r) * sin_theta_h.powf(inv_r) / (2.0 * PI);
+        let v = 1.0 / (4.0 * (wi.z() + wo.z() - wi.z() * wo.z()));
+        d * v
+    }
+
+    // Midpoint rule integral of the lobe over the hemisphere seen from cos_o
+    fn directional_albedo(roughness: f64, cos_o: f64) -> f64 {
+        let wo = Vec3::new(((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o));
+        let n = ALBEDO_SAMPLES as f64;
+        let mut sum = 0.0;
+        for i in 0..ALBEDO_SAMPLES {
+            let z = (i as f64 + 0.5) / n;
+            let r = (1.0 - z * z).sqrt();
+            for j in 0..ALBEDO_SAMPLES {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n;
+                let wi = Vec3::new((r * phi.cos(), r * phi.sin(), z));
+                sum += Sheen::lobe(roughness, wo, wi) * z;
+            }
+        }
+        (sum * 2.0 * PI / (n * n)).min(1.0)
+    }
+
+    fn sheen_albedo(&self, cosine: f64) -> f64 {
+        let x = (cosine.clamp(0.0, 1.0) * ALBEDO_ENTRIES as f64 - 0.5).max(0.0);
+        let i = (x as usize).min(ALBEDO_ENTRIES - 1);
+        let j = (i + 1).min(ALBEDO_ENTRIES - 1);
+        let t = (x - i as f64).min(1.0);
+        (1.0 - t) * self.sheen_albedo[i] + t * self.sheen_albedo[j]
+    }
+
+    // BRDF for local directions above the surface
+    fn f(&self, wo: Vec3, wi: Vec3) -> Color {
+        // scaling on both sides keeps the base reciprocal
+        let white = Color::new((1.0, 1.0, 1.0));
+        let base = (white - self.sheen_albedo(wo.z()) * self.sheen_color)
+            * (white - self.sheen_albedo(wi.z()) * self.sheen_color);
+        base * self.albedo / PI + Sheen::lobe(self.roughness, wo, wi) * self.sheen_color
+    }
+
+    // probability of sampling the diffuse lobe, the rest goes to the sheen
+    fn diffuse_probability(&self) -> f64 {
+        let diffuse = self.albedo.luminance();
+        let sheen = self.sheen_color.luminance();
+        if diffuse + sheen > 0.0 {diffuse / (diffuse + sheen)} else {1.0}
+    }
+
+    // diffuse lobe is cosine sampled, sheen lobe uniformly over the hemisphere
+    fn local_pdf(&self, wi: Vec3) -> f64 {
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let p = self.diffuse_probability();
+        p * wi.z() / PI + (1.0 - p) / (2.0 * PI)
+    }
+}
+
+impl Material for Sheen {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        if wo.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        let mut rng = random::rng();
+        let wi = if rng.gen_range(0.0, 1.0) < self.diffuse_probability() {
+            Vec3::random_cosine_direction()
+        } else {
+            let z: f64 = rng.gen_range(0.0, 1.0);
+            let r = (1.0 - z * z).max(0.0).sqrt();
+            let phi = 2.0 * PI * rng.gen_range(0.0, 1.0);
+            Vec3::new((r * phi.cos(), r * phi.sin(), z))
+        };
+        let pdf = self.local_pdf(wi);
+        if pdf <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+        let attenuation = self.f(wo, wi) * (wi.z() / pdf);
+        (attenuation, Ray::new(rec.p(), uvw.local(wi)), true)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        self.f(wo, wi) * wi.z()
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let uvw = Onb::build_from_w(rec.normal());
+        self.local_pdf(uvw.to_local(wi))
+    }
+}
@@ -0,0 +1,62 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_dielectric;
+use crate::gfx::medium::Medium;
+use crate::gfx::medium::homogeneous::Homogeneous;
+use crate::gfx::medium::homogeneous::HomogeneousFn;
+
+// Translucent solid such as skin, marble or wax. The smooth dielectric boundary
+// lets light in and the integrator random walks it through the interior medium,
+// every scattering event inside uses up one bounce of the path's depth.
+pub struct Subsurface {
+    ref_idx: f64,
+    interior: Homogeneous
+}
+
+pub trait SubsurfaceFn : Material {
+    // mean_free_path is the average distance per channel between interactions
+    // in world units, anisotropy is the Henyey-Greenstein g
+    fn new(albedo: Color, mean_free_path: Color, anisotropy: f64) -> Self;
+    fn new_with_ior(albedo: Color, mean_free_path: Color, anisotropy: f64, ref_idx: f64) -> Self;
+}
+
+impl SubsurfaceFn for Subsurface {
+    fn new(albedo: Color, mean_free_path: Color, anisotropy: f64) -> Self {
+        Subsurface::new_with_ior(albedo, mean_free_path, anisotropy, 1.4)
+    }
+
+    fn new_with_ior(albedo: Color, mean_free_path: Color, anisotropy: f64, ref_idx: f64) -> Self {
+        Subsurface { ref_idx, interior: Homogeneous::from_albedo(albedo, mean_free_path, anisotropy) }
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let etai_over_etat = if rec.front() {1.0 / self.ref_idx} else {self.ref_idx};
+        let unit_direction = r_in.direction().unitize();
+        let cos_theta = (-unit_direction.dot(rec.normal())).min(1.0);
+        let reflect_prob = fresnel_dielectric(cos_theta, 1.0 / etai_over_etat);
+
+        let mut rng = random::rng();
+        let direction = if rng.gen_range(0.0, 1.0) < reflect_prob {
+            unit_direction.reflect(rec.normal())
+        } else {
+            unit_direction.refract(rec.normal(), etai_over_etat)
+        };
+        (Color::new((1.0, 1.0, 1.0)), Ray::new(rec.p(), direction), true)
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        Some(&self.interior)
+    }
+}
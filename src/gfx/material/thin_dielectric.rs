@@ -0,0 +1,60 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_dielectric;
+use crate::gfx::material::beer_lambert;
+use crate::gfx::material::absorption_from_tint;
+
+// Infinitely thin glass sheet such as a window pane. Both interfaces are
+// folded into one, so transmitted rays keep their direction.
+pub struct ThinDielectric {
+    ref_idx: f64,
+    absorption: Color
+}
+
+pub trait ThinDielectricFn : Material {
+    fn new(ref_idx: f64) -> Self;
+    // tint is the color transmitted through the pane at normal incidence
+    fn new_tinted(ref_idx: f64, tint: Color) -> Self;
+}
+
+impl ThinDielectricFn for ThinDielectric {
+    fn new(ref_idx: f64) -> Self {
+        ThinDielectric { ref_idx, absorption: Color::new((0.0, 0.0, 0.0)) }
+    }
+
+    fn new_tinted(ref_idx: f64, tint: Color) -> Self {
+        ThinDielectric { ref_idx, absorption: absorption_from_tint(tint, 1.0) }
+    }
+}
+
+impl Material for ThinDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let unit_direction = r_in.direction().unitize();
+        let cos_theta = (-unit_direction.dot(rec.normal())).min(1.0);
+        let r = fresnel_dielectric(cos_theta, self.ref_idx);
+        // sum of all inter-reflections between the two faces of the sheet
+        let reflect_prob = if r < 1.0 {2.0 * r / (1.0 + r)} else {1.0};
+
+        let mut rng = random::rng();
+        if rng.gen_range(0.0, 1.0) < reflect_prob {
+            let reflected = unit_direction.reflect(rec.normal());
+            return (Color::new((1.0, 1.0, 1.0)), Ray::new(rec.p(), reflected), true);
+        }
+
+        // absorption grows with the slanted path length through the sheet
+        let sin2_t = (1.0 - cos_theta * cos_theta) / (self.ref_idx * self.ref_idx);
+        let cos_t = (1.0 - sin2_t).max(1e-4).sqrt();
+        let attenuation = beer_lambert(self.absorption, 1.0 / cos_t);
+        (attenuation, Ray::new(rec.p(), unit_direction), true)
+    }
+}
@@ -0,0 +1,169 @@
+use std::rc::Rc;
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::ColorTraits;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+use crate::basic_types::spectrum;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::hittable::HitRecord;
+use crate::gfx::hittable::HitRecordTraits;
+use crate::gfx::material::Material;
+use crate::gfx::material::fresnel_thin_film;
+use crate::gfx::material::microfacet::Ggx;
+use crate::gfx::material::microfacet::GgxTraits;
+use crate::gfx::texture::Texture;
+
+// Wavelengths used to fold the film's reflectance spectrum into RGB
+const RGB_SAMPLES: i32 = 40;
+
+enum Substrate {
+    // smooth dielectric the film sits on, an IOR of 1 gives a free film such as a soap bubble
+    Dielectric(f64),
+    // complex IOR of a metal under an oxide or oil layer
+    Conductor(Color, Color)
+}
+
+// Iridescent coating whose interference colors come from the Airy reflectance of
+// a film, thickness is read in nanometers from the first channel of a texture
+pub struct ThinFilm {
+    substrate: Substrate,
+    distribution: Ggx,
+    film_ior: f64,
+    thickness: Rc<dyn Texture>
+}
+
+pub trait ThinFilmFn : Material {
+    fn new_dielectric(ref_idx: f64, film_ior: f64, thickness: Rc<dyn Texture>) -> Self;
+    fn new_conductor(eta: Color, k: Color, roughness: f64, film_ior: f64, thickness: Rc<dyn Texture>) -> Self;
+    fn soap_bubble(thickness: Rc<dyn Texture>) -> Self;
+}
+
+impl ThinFilmFn for ThinFilm {
+    fn new_dielectric(ref_idx: f64, film_ior: f64, thickness: Rc<dyn Texture>) -> Self {
+        ThinFilm { substrate: Substrate::Dielectric(ref_idx), distribution: Ggx::new(0.0, 0.0), film_ior, thickness }
+    }
+
+    fn new_conductor(eta: Color, k: Color, roughness: f64, film_ior: f64, thickness: Rc<dyn Texture>) -> Self {
+        let distribution = Ggx::from_roughness(roughness, roughness);
+        ThinFilm { substrate: Substrate::Conductor(eta, k), distribution, film_ior, thickness }
+    }
+
+    fn soap_bubble(thickness: Rc<dyn Texture>) -> Self {
+        ThinFilm::new_dielectric(1.0, 1.33, thickness)
+    }
+}
+
+impl ThinFilm {
+    // Reflectance at the ray's wavelength in spectral mode, otherwise the RGB color
+    // of the whole reflected spectrum. eta_i is the IOR on the incident side.
+    fn reflectance(&self, r_in: &Ray, rec: &HitRecord, cos_theta: f64, eta_i: f64, eta_t: Color, k_t: Color) -> Color {
+        let thickness = self.thickness.scalar(rec.u(), rec.v(), rec.p()).max(0.0);
+        let at = |lambda: f64| {
+            fresnel_thin_film(cos_theta, lambda, thickness, eta_i, self.film_ior,
+                spectrum::rgb_to_spectrum(eta_t, lambda), spectrum::rgb_to_spectrum(k_t, lambda))
+        };
+        match r_in.wavelength() {
+            Some(lambda) => {
+                let r = at(lambda);
+                Color::new((r, r, r))
+            }
+            None => {
+                // saturated interference colors can fall outside the RGB gamut
+                let c = spectrum::reflectance_to_rgb(at, RGB_SAMPLES);
+                Color::new((c.x().clamp(0.0, 1.0), c.y().clamp(0.0, 1.0), c.z().clamp(0.0, 1.0)))
+            }
+        }
+    }
+
+    fn scatter_dielectric(&self, ref_idx: f64, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        // the film is on the outside, so rays leaving the substrate cross it too
+        let (eta_i, eta_t) = if rec.front() {(1.0, ref_idx)} else {(ref_idx, 1.0)};
+        let unit_direction = r_in.direction().unitize();
+        let cos_theta = (-unit_direction.dot(rec.normal())).min(1.0);
+        let eta_t = Color::new((eta_t, eta_t, eta_t));
+        let reflectance = self.reflectance(r_in, rec, cos_theta, eta_i, eta_t, Color::new((0.0, 0.0, 0.0)));
+
+        let reflected = Ray::new(rec.p(), unit_direction.reflect(rec.normal()));
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let etai_over_etat = eta_i / eta_t.x();
+        if etai_over_etat * sin_theta > 1.0 {
+            return (Color::new((1.0, 1.0, 1.0)), reflected, true);
+        }
+
+        // pick a branch by the average reflectance and reweight each channel
+        let reflect_prob = reflectance.luminance().clamp(0.01, 0.99);
+        let mut rng = random::rng();
+        if rng.gen_range(0.0, 1.0) < reflect_prob {
+            return (reflectance / reflect_prob, reflected, true);
+        }
+        let transmittance = Color::new((1.0, 1.0, 1.0)) - reflectance;
+        let refracted = Ray::new(rec.p(), unit_direction.refract(rec.normal(), etai_over_etat));
+        (transmittance / (1.0 - reflect_prob), refracted, true)
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> (Color, Ray, bool) {
+        let (eta, k) = match self.substrate {
+            Substrate::Dielectric(ref_idx) => return self.scatter_dielectric(ref_idx, r_in, rec),
+            Substrate::Conductor(eta, k) => (eta, k)
+        };
+
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        if wo.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        let mut rng = random::rng();
+        let wm = self.distribution.sample_visible_normal(wo, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+        let wi = (-wo).reflect(wm);
+        if wi.z() <= 0.0 {
+            return (Color::new((0.0, 0.0, 0.0)), *r_in, false);
+        }
+
+        let fresnel = self.reflectance(r_in, rec, wo.dot(wm), 1.0, eta, k);
+        let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        let scattered = Ray::new(rec.p(), uvw.local(wi));
+        (weight * fresnel, scattered, true)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let (eta, k) = match self.substrate {
+            Substrate::Dielectric(_) => return Color::new((0.0, 0.0, 0.0)),
+            Substrate::Conductor(eta, k) => (eta, k)
+        };
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new((0.0, 0.0, 0.0));
+        }
+        let wm = (wo + wi).unitize();
+        let fresnel = self.reflectance(r_in, rec, wo.dot(wm), 1.0, eta, k);
+        let value = self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z());
+        value * fresnel
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        if let Substrate::Dielectric(_) = self.substrate {
+            return 0.0;
+        }
+        let uvw = Onb::build_from_w(rec.normal());
+        let wo = uvw.to_local(-r_in.direction().unitize());
+        let wi = uvw.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unitize();
+        self.distribution.pdf(wo, wm) / (4.0 * wo.dot(wm))
+    }
+}
@@ -0,0 +1,50 @@
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::onb::Onb;
+use crate::basic_types::onb::OnbTraits;
+
+// Phase function with a single anisotropy parameter g in (-1, 1),
+// positive values scatter forward and 0 is isotropic
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HenyeyGreenstein {
+    g: f64
+}
+
+pub trait HenyeyGreensteinTraits {
+    fn new(g: f64) -> Self;
+    fn g(&self) -> f64;
+    // Density over solid angle for the cosine between the old and new directions
+    fn p(&self, cos_theta: f64) -> f64;
+    // New direction for light travelling along the unit vector direction
+    fn sample(&self, direction: Vec3, u1: f64, u2: f64) -> Vec3;
+}
+
+impl HenyeyGreensteinTraits for HenyeyGreenstein {
+    fn new(g: f64) -> Self {
+        HenyeyGreenstein { g: g.clamp(-0.99, 0.99) }
+    }
+
+    fn g(&self) -> f64 {
+        self.g
+    }
+
+    fn p(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * std::f64::consts::PI * denom * denom.sqrt())
+    }
+
+    fn sample(&self, direction: Vec3, u1: f64, u2: f64) -> Vec3 {
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u1);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u2;
+        let uvw = Onb::build_from_w(direction);
+        uvw.local(Vec3::new((sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)))
+    }
+}
@@ -0,0 +1,106 @@
+use rand::Rng;
+use crate::basic_types::random;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::spectrum;
+
+use crate::basic_types::ray::Ray;
+use crate::basic_types::ray::RayTraits;
+
+use crate::gfx::medium::Medium;
+use crate::gfx::medium::henyey_greenstein::HenyeyGreenstein;
+use crate::gfx::medium::henyey_greenstein::HenyeyGreensteinTraits;
+
+// Medium with constant absorption and scattering coefficients per color channel,
+// in inverse world units
+pub struct Homogeneous {
+    sigma_a: Color,
+    sigma_s: Color,
+    phase: HenyeyGreenstein
+}
+
+pub trait HomogeneousFn : Medium {
+    fn new(sigma_a: Color, sigma_s: Color, g: f64) -> Self;
+    // Single scattering albedo and the average distance between interactions
+    fn from_albedo(albedo: Color, mean_free_path: Color, g: f64) -> Self;
+}
+
+impl HomogeneousFn for Homogeneous {
+    fn new(sigma_a: Color, sigma_s: Color, g: f64) -> Self {
+        Homogeneous { sigma_a, sigma_s, phase: HenyeyGreenstein::new(g) }
+    }
+
+    fn from_albedo(albedo: Color, mean_free_path: Color, g: f64) -> Self {
+        let sigma_t = Color::new((1.0 / mean_free_path.x().max(1e-9),
+            1.0 / mean_free_path.y().max(1e-9),
+            1.0 / mean_free_path.z().max(1e-9)));
+        let sigma_s = albedo * sigma_t;
+        Homogeneous::new(sigma_t - sigma_s, sigma_s, g)
+    }
+}
+
+fn transmittance(sigma_t: Color, distance: f64) -> Color {
+    Color::new(((-sigma_t.x() * distance).exp(),
+        (-sigma_t.y() * distance).exp(),
+        (-sigma_t.z() * distance).exp()))
+}
+
+fn channel(c: Color, i: usize) -> f64 {
+    match i {
+        0 => c.x(),
+        1 => c.y(),
+        _ => c.z()
+    }
+}
+
+// Probability of sampling with each channel, proportional to the path throughput
+fn channel_probabilities(throughput: Color) -> Color {
+    let t = Color::new((throughput.x().abs(), throughput.y().abs(), throughput.z().abs()));
+    let sum = t.x() + t.y() + t.z();
+    if sum > 0.0 {t / sum} else {Color::new((1.0, 1.0, 1.0)) / 3.0}
+}
+
+impl Medium for Homogeneous {
+    fn sample(&self, r: &Ray, t_max: f64, throughput: Color) -> (Color, Option<Ray>) {
+        let length = r.direction().length();
+        let direction = r.direction() / length;
+        let distance = t_max * length;
+        let sigma_t = self.sigma_a + self.sigma_s;
+        let mut rng = random::rng();
+        let u: f64 = rng.gen_range(0.0, 1.0);
+
+        let (weight, travelled) = match r.wavelength() {
+            // spectral paths see a single extinction, so plain free flight sampling is exact
+            Some(lambda) => {
+                let sigma_t = spectrum::rgb_to_spectrum(sigma_t, lambda);
+                let sigma_s = spectrum::rgb_to_spectrum(self.sigma_s, lambda);
+                let t = if sigma_t > 0.0 {-(1.0 - u).ln() / sigma_t} else {f64::INFINITY};
+                if t >= distance {
+                    return (Color::new((1.0, 1.0, 1.0)), None);
+                }
+                let albedo = sigma_s / sigma_t;
+                (Color::new((albedo, albedo, albedo)), t)
+            }
+            // chromatic media pick a channel to sample with and weight by the
+            // density mixed over all three (spectral MIS)
+            None => {
+                let probabilities = channel_probabilities(throughput);
+                let pick: f64 = rng.gen_range(0.0, 1.0);
+                let c = if pick < probabilities.x() {0} else if pick < probabilities.x() + probabilities.y() {1} else {2};
+                let sigma_c = channel(sigma_t, c);
+                let t = if sigma_c > 0.0 {-(1.0 - u).ln() / sigma_c} else {f64::INFINITY};
+                if t >= distance {
+                    let tr = transmittance(sigma_t, distance);
+                    return (tr / probabilities.dot(tr), None);
+                }
+                let tr = transmittance(sigma_t, t);
+                (self.sigma_s * tr / probabilities.dot(sigma_t * tr), t)
+            }
+        };
+
+        let origin = r.origin() + travelled * direction;
+        let scattered = self.phase.sample(direction, rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+        (weight, Some(Ray::new(origin, scattered).with_wavelength(r.wavelength())))
+    }
+}
@@ -0,0 +1,15 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::ray::Ray;
+
+// Participating media filling the inside of closed objects
+pub trait Medium {
+    // Samples where r next interacts before reaching the boundary at t_max.
+    // Returns the throughput weight and, when the ray scatters inside the medium,
+    // the new ray leaving the scattering point. None means it reached t_max.
+    // throughput is the path's weight so far, chromatic media use it to pick
+    // which channel's density to sample distances from.
+    fn sample(&self, r: &Ray, t_max: f64, throughput: Color) -> (Color, Option<Ray>);
+}
+
+pub mod henyey_greenstein;
+pub mod homogeneous;
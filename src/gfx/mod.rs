@@ -1,4 +1,19 @@
-pub mod screen;
-pub mod hittable;
-pub mod camera;
-pub mod material;
\ No newline at end of file
+pub mod screen;
+pub mod hittable;
+pub mod camera;
+pub mod material;
+pub mod texture;
+pub mod denoise;
+pub mod integrator;
+pub mod medium;
+pub mod light;
+pub mod compare;
+pub mod scene;
+pub mod renderer;
+pub mod stats;
+pub mod progress;
+pub mod terminal;
+pub mod server;
+pub mod tile;
+pub mod film;
+pub mod distributed;
@@ -0,0 +1,113 @@
+use std::io::IsTerminal;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::gfx::renderer::Progress;
+use crate::gfx::renderer::ProgressTraits;
+
+use crate::gfx::stats;
+use crate::gfx::stats::RenderStatsTraits;
+
+// Width of the bar drawn in interactive mode
+const BAR_WIDTH: usize = 30;
+// Shortest time between redraws of the interactive line
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+// Log mode writes a line each time this fraction of the image is finished
+const LOG_STEP: f64 = 0.1;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReportStyle {
+    // one line redrawn in place, for a terminal
+    Interactive,
+    // a line every LOG_STEP of the image, for files and CI logs
+    Log,
+    Silent
+}
+
+pub trait ReportStyleTraits {
+    // Interactive when stderr is a terminal, plain log lines otherwise
+    fn detect() -> Self;
+}
+
+impl ReportStyleTraits for ReportStyle {
+    fn detect() -> Self {
+        if std::io::stderr().is_terminal() {ReportStyle::Interactive} else {ReportStyle::Log}
+    }
+}
+
+// Time left for a render that has done fraction of its work in elapsed,
+// None before there is anything to go by
+pub fn eta(elapsed: Duration, fraction: f64) -> Option<Duration> {
+    if fraction >= 1.0 {
+        Some(Duration::from_secs(0))
+    } else if fraction > 0.0 {
+        Some(elapsed.mul_f64((1.0 - fraction) / fraction))
+    } else {
+        None
+    }
+}
+
+// Turns the renderer's progress callbacks into percent done, elapsed time,
+// ETA and ray throughput on stderr
+pub struct ProgressReporter {
+    style: ReportStyle,
+    last_redraw: Option<Instant>,
+    next_log: f64
+}
+
+pub trait ProgressReporterTraits {
+    fn new(style: ReportStyle) -> Self;
+    fn report(&mut self, progress: &Progress);
+    // Ends the interactive line so later output starts on a fresh one
+    fn finish(&mut self);
+}
+
+impl ProgressReporter {
+    fn status(&self, progress: &Progress) -> String {
+        let done = progress.fraction();
+        let elapsed = progress.elapsed;
+        let eta = eta(elapsed, done).map_or("?".to_string(), stats::duration);
+        let rays_per_second = progress.stats.total_rays() as f64 / elapsed.as_secs_f64().max(1e-9);
+        format!("{:5.1}%  elapsed {}  ETA {}  {} rays/s", 100.0 * done, stats::duration(elapsed), eta, stats::si(rays_per_second))
+    }
+}
+
+impl ProgressReporterTraits for ProgressReporter {
+    fn new(style: ReportStyle) -> Self {
+        ProgressReporter { style, last_redraw: None, next_log: LOG_STEP }
+    }
+
+    fn report(&mut self, progress: &Progress) {
+        let done = progress.fraction();
+        match self.style {
+            ReportStyle::Interactive => {
+                let now = Instant::now();
+                let due = self.last_redraw.is_none_or(|last| now - last >= REDRAW_INTERVAL);
+                if due || done >= 1.0 {
+                    self.last_redraw = Some(now);
+                    let filled = (done * BAR_WIDTH as f64) as usize;
+                    let bar = format!("{}{}", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled));
+                    // trailing spaces clear what a longer previous line left behind
+                    eprint!("\r[{}] {}    ", bar, self.status(progress));
+                    let _ = std::io::stderr().flush();
+                }
+            }
+            ReportStyle::Log => {
+                if done + 1e-9 >= self.next_log {
+                    while self.next_log <= done + 1e-9 {
+                        self.next_log += LOG_STEP;
+                    }
+                    eprintln!("render {}", self.status(progress));
+                }
+            }
+            ReportStyle::Silent => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.style == ReportStyle::Interactive && self.last_redraw.is_some() {
+            eprintln!();
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::Rng;
+
+use crate::basic_types::random;
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::camera::CameraTraits;
+
+use crate::gfx::denoise::FeatureBuffers;
+use crate::gfx::denoise::FeatureBuffersTraits;
+
+use crate::gfx::integrator::ColorMode;
+use crate::gfx::integrator::sample_color;
+use crate::gfx::integrator::sample_color_with_features;
+
+use crate::gfx::scene::Scene;
+
+use crate::gfx::film::Film;
+use crate::gfx::film::FilmTraits;
+use crate::gfx::film::TileResult;
+
+use crate::gfx::screen::Screen;
+
+use crate::gfx::stats;
+use crate::gfx::stats::RenderStats;
+use crate::gfx::stats::RenderStatsTraits;
+
+use crate::gfx::tile;
+use crate::gfx::tile::Tile;
+use crate::gfx::tile::TileTraits;
+use crate::gfx::tile::TileOrder;
+
+// Image size and sampling of a render, everything else comes from the Scene
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub width: i32,
+    pub height: i32,
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    // renders with the same seed and settings produce the same image
+    pub seed: u64,
+    // samples are spread over this many sweeps of the whole image, each
+    // ending with a viewable picture. The result doesn't depend on it.
+    pub passes: i32,
+    // crop window, only its pixels are rendered and the rest of the image stays black
+    pub region: Option<Tile>,
+    // each pass is rendered a tile at a time, whole rows of the region if None
+    pub tile_size: Option<i32>,
+    pub tile_order: TileOrder,
+    pub mode: ColorMode,
+    // also gather the albedo, normal and depth buffers that guide the denoiser
+    pub features: bool
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 384,
+            height: 216,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            seed: 0,
+            passes: 1,
+            region: None,
+            tile_size: None,
+            tile_order: TileOrder::Scanline,
+            mode: ColorMode::Rgb,
+            features: false
+        }
+    }
+}
+
+pub trait RenderSettingsTraits {
+    // Pixels to render, the crop window clipped to the image
+    fn region(&self) -> Tile;
+    // Tiles of the region in the order a pass renders them
+    fn tiles(&self) -> Vec<Tile>;
+}
+
+impl RenderSettingsTraits for RenderSettings {
+    fn region(&self) -> Tile {
+        let image = Tile::image(self.width, self.height);
+        self.region.map_or(image, |region| region.intersection(&image))
+    }
+
+    fn tiles(&self) -> Vec<Tile> {
+        let region = self.region();
+        match self.tile_size {
+            Some(size) => tile::schedule(region, size, size, self.tile_order),
+            None => tile::schedule(region, region.width(), 1, self.tile_order)
+        }
+    }
+}
+
+// How far a render has got, reported after every finished tile
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Progress {
+    // pixels of every pass together
+    pub pixels_done: usize,
+    pub pixels: usize,
+    pub passes_done: i32,
+    pub passes: i32,
+    // work done by the render so far
+    pub stats: RenderStats,
+    // time spent rendering so far, not counting pauses
+    pub elapsed: Duration
+}
+
+pub trait ProgressTraits {
+    fn fraction(&self) -> f64;
+}
+
+impl ProgressTraits for Progress {
+    fn fraction(&self) -> f64 {
+        if self.pixels == 0 {1.0} else {self.pixels_done as f64 / self.pixels as f64}
+    }
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    cancelled: bool,
+    paused_since: Option<Instant>,
+    // length of the pauses that have ended
+    paused_for: Duration
+}
+
+// Cancels, pauses and resumes a render from any thread. Clones share their
+// state, the render looks at it between tiles.
+#[derive(Debug, Clone, Default)]
+pub struct RenderControl {
+    state: Arc<(Mutex<ControlState>, Condvar)>
+}
+
+pub trait RenderControlTraits {
+    fn new() -> Self;
+    fn cancel(&self);
+    fn pause(&self);
+    fn resume(&self);
+    fn is_cancelled(&self) -> bool;
+    fn is_paused(&self) -> bool;
+    // Everything spent paused so far, including a pause still going on
+    fn paused_time(&self) -> Duration;
+    // Starts measuring the time from now on that the render is not paused
+    fn timer(&self) -> ActiveTimer;
+    // Blocks for as long as the render is paused, then tells whether it was cancelled
+    fn wait_while_paused(&self) -> bool;
+}
+
+impl RenderControl {
+    fn update<F: FnOnce(&mut ControlState)>(&self, f: F) {
+        let (state, changed) = &*self.state;
+        f(&mut state.lock().unwrap());
+        changed.notify_all();
+    }
+}
+
+impl RenderControlTraits for RenderControl {
+    fn new() -> Self {
+        RenderControl::default()
+    }
+
+    fn cancel(&self) {
+        self.update(|state| state.cancelled = true)
+    }
+
+    fn pause(&self) {
+        self.update(|state| {
+            state.paused_since.get_or_insert_with(Instant::now);
+        })
+    }
+
+    fn resume(&self) {
+        self.update(|state| if let Some(since) = state.paused_since.take() {
+            state.paused_for += since.elapsed();
+        })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.state.0.lock().unwrap().cancelled
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.0.lock().unwrap().paused_since.is_some()
+    }
+
+    fn paused_time(&self) -> Duration {
+        let state = self.state.0.lock().unwrap();
+        state.paused_for + state.paused_since.map_or(Duration::from_secs(0), |since| since.elapsed())
+    }
+
+    fn timer(&self) -> ActiveTimer {
+        ActiveTimer { control: self.clone(), start: Instant::now(), paused_before: self.paused_time() }
+    }
+
+    fn wait_while_paused(&self) -> bool {
+        let (state, changed) = &*self.state;
+        let state = changed.wait_while(state.lock().unwrap(), |state| state.paused_since.is_some() && !state.cancelled).unwrap();
+        state.cancelled
+    }
+}
+
+// Time since it was started by RenderControl::timer, less the pauses in between,
+// for rates and ETAs that a pause doesn't skew
+#[derive(Debug, Clone)]
+pub struct ActiveTimer {
+    control: RenderControl,
+    start: Instant,
+    paused_before: Duration
+}
+
+pub trait ActiveTimerTraits {
+    fn elapsed(&self) -> Duration;
+}
+
+impl ActiveTimerTraits for ActiveTimer {
+    fn elapsed(&self) -> Duration {
+        let paused = self.control.paused_time().saturating_sub(self.paused_before);
+        self.start.elapsed().saturating_sub(paused)
+    }
+}
+
+// What a render produced. Pixels of a cancelled render average the samples
+// they got, ones it never reached are left black like those outside the region.
+pub struct RenderOutput {
+    pub screen: Screen,
+    pub features: Option<FeatureBuffers>,
+    pub stats: RenderStats,
+    // time spent rendering, not counting pauses
+    pub elapsed: Duration,
+    pub cancelled: bool
+}
+
+type ProgressCallback<'a> = Box<dyn FnMut(Progress) + 'a>;
+type PassCallback<'a> = Box<dyn FnMut(&Screen, Progress) + 'a>;
+
+pub struct Renderer<'a> {
+    scene: &'a Scene,
+    settings: RenderSettings,
+    progress: Option<ProgressCallback<'a>>,
+    pass_done: Option<PassCallback<'a>>,
+    control: RenderControl
+}
+
+pub trait RendererTraits<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self;
+    fn settings(&self) -> &RenderSettings;
+    // Called on the rendering thread after every tile
+    fn on_progress<F: FnMut(Progress) + 'a>(&mut self, callback: F);
+    // Called with the image so far at the end of every pass
+    fn on_pass<F: FnMut(&Screen, Progress) + 'a>(&mut self, callback: F);
+    fn control(&self) -> RenderControl;
+    fn render(&mut self) -> RenderOutput;
+    // Every sample of the pixels in tile, or None if cancelled first
+    fn render_tile(&mut self, tile: Tile) -> Option<TileResult>;
+}
+
+impl<'a> RendererTraits<'a> for Renderer<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        Renderer { scene, settings, progress: None, pass_done: None, control: RenderControl::new() }
+    }
+
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    fn on_progress<F: FnMut(Progress) + 'a>(&mut self, callback: F) {
+        self.progress = Some(Box::new(callback));
+    }
+
+    fn on_pass<F: FnMut(&Screen, Progress) + 'a>(&mut self, callback: F) {
+        self.pass_done = Some(Box::new(callback));
+    }
+
+    fn control(&self) -> RenderControl {
+        self.control.clone()
+    }
+
+    fn render(&mut self) -> RenderOutput {
+        let RenderSettings { width, height, samples_per_pixel, seed, .. } = self.settings;
+        let passes = self.settings.passes.clamp(1, samples_per_pixel.max(1));
+        let mut film = Film::new(width, height);
+        // each pixel's random stream, so a later pass carries on where the last one stopped
+        let mut generators = Vec::with_capacity((width.max(0) * height.max(0)) as usize);
+        for j in 0..height {
+            for i in 0..width {
+                generators.push(random::seeded(random::pixel_seed(seed, i, j)));
+            }
+        }
+        let mut features = if self.settings.features {Some(FeatureBuffers::new(width, height))} else {None};
+        let tiles = self.settings.tiles();
+        let pass_pixels: usize = tiles.iter().map(Tile::pixels).sum();
+        let pixels = passes as usize * pass_pixels;
+        let start = stats::snapshot();
+        let timer = self.control.timer();
+        let mut pixels_done = 0;
+        for pass in 0..passes {
+            let samples = samples_per_pixel / passes + if pass < samples_per_pixel % passes {1} else {0};
+            for tile in &tiles {
+                if self.control.wait_while_paused() {
+                    return RenderOutput { screen: film.screen(), features, stats: stats::snapshot().since(&start), elapsed: timer.elapsed(), cancelled: true };
+                }
+                for (i, j) in tile.coordinates() {
+                    let pixel_color = random::with_generator(&mut generators[(j * width + i) as usize], || {
+                        self.render_pixel(i, j, samples, features.as_mut())
+                    });
+                    film.add_samples(i, j, pixel_color, samples);
+                }
+                pixels_done += tile.pixels();
+                if let Some(callback) = self.progress.as_mut() {
+                    callback(Progress { pixels_done, pixels, passes_done: pass, passes, stats: stats::snapshot().since(&start), elapsed: timer.elapsed() });
+                }
+            }
+            if let Some(callback) = self.pass_done.as_mut() {
+                let progress = Progress { pixels_done, pixels, passes_done: pass + 1, passes, stats: stats::snapshot().since(&start), elapsed: timer.elapsed() };
+                callback(&film.screen(), progress);
+            }
+        }
+        RenderOutput { screen: film.screen(), features, stats: stats::snapshot().since(&start), elapsed: timer.elapsed(), cancelled: false }
+    }
+
+    fn render_tile(&mut self, tile: Tile) -> Option<TileResult> {
+        let RenderSettings { samples_per_pixel, seed, .. } = self.settings;
+        let start = stats::snapshot();
+        let mut sums = Vec::with_capacity(tile.pixels());
+        for (i, j) in tile.coordinates() {
+            if i == tile.x0 && self.control.wait_while_paused() {
+                return None;
+            }
+            // seeded as in a full render, so the tile matches those pixels of one exactly
+            random::seed(random::pixel_seed(seed, i, j));
+            sums.push(self.render_pixel(i, j, samples_per_pixel, None));
+        }
+        Some(TileResult { tile, samples_per_pixel, sums, stats: stats::snapshot().since(&start) })
+    }
+}
+
+impl<'a> Renderer<'a> {
+    // Sum of the next samples drawn through pixel (i, j), using this thread's generator
+    fn render_pixel(&self, i: i32, j: i32, samples: i32, mut features: Option<&mut FeatureBuffers>) -> Color {
+        let RenderSettings { width, height, max_depth, mode, .. } = self.settings;
+        let scene = self.scene;
+        let mut rng = random::rng();
+        let mut pixel_color = Color::new((0.0, 0.0, 0.0));
+        // a single row or column spans the whole view instead of dividing by zero
+        let (u_scale, v_scale) = ((width.max(2) - 1) as f64, (height.max(2) - 1) as f64);
+        for _ in 0..samples {
+            let u = (i as f64 + rng.gen_range(0.0, 1.0)) / u_scale;
+            let v = (j as f64 + rng.gen_range(0.0, 1.0)) / v_scale;
+            let r = scene.camera.get_ray(u, v);
+            stats::count_camera_ray();
+            let sample = match features.as_mut() {
+                Some(features) => {
+                    let (sample, primary) = sample_color_with_features(r, &scene.world, &scene.lights, &scene.background, max_depth, mode);
+                    features.add_sample(i as usize, j as usize, sample, primary.albedo, primary.normal, primary.depth);
+                    sample
+                }
+                None => sample_color(r, &scene.world, &scene.lights, &scene.background, max_depth, mode)
+            };
+            pixel_color += sample;
+        }
+        pixel_color
+    }
+}
@@ -0,0 +1,248 @@
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::basic_types::random;
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::camera::Camera;
+use crate::gfx::camera::CameraTraits;
+
+use crate::gfx::hittable::hittables::Hittable;
+use crate::gfx::hittable::hittables::hittable_list::HittableList;
+use crate::gfx::hittable::hittables::hittable_list::HittableListTrait;
+use crate::gfx::hittable::hittables::instance::Instance;
+use crate::gfx::hittable::hittables::instance::InstanceData;
+use crate::gfx::hittable::hittables::quad::Quad;
+use crate::gfx::hittable::hittables::quad::QuadData;
+use crate::gfx::hittable::hittables::quad::cuboid;
+use crate::gfx::hittable::hittables::sphere::Sphere;
+use crate::gfx::hittable::hittables::sphere::SphereData;
+
+use crate::gfx::integrator::Background;
+
+use crate::gfx::light::Light;
+use crate::gfx::light::directional::DirectionalLight;
+use crate::gfx::light::directional::DirectionalLightFn;
+
+use crate::gfx::material::Material;
+use crate::gfx::material::conductor::Conductor;
+use crate::gfx::material::conductor::ConductorFn;
+use crate::gfx::material::dielectric::Dielectric;
+use crate::gfx::material::dielectric::DielectricFn;
+use crate::gfx::material::diffuse_light::DiffuseLight;
+use crate::gfx::material::diffuse_light::DiffuseLightFn;
+use crate::gfx::material::lambertian::Lambertian;
+use crate::gfx::material::lambertian::LambertianFn;
+
+use crate::gfx::texture::Texture;
+use crate::gfx::texture::checker::CheckerTexture;
+use crate::gfx::texture::checker::CheckerTextureFn;
+use crate::gfx::texture::solid_color::SolidColor;
+use crate::gfx::texture::solid_color::SolidColorFn;
+
+// Everything needed to render one image apart from the render settings
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    pub lights: Vec<Rc<dyn Light>>,
+    pub background: Background
+}
+
+// Presets selectable by name, in the order they are listed to the user
+pub const PRESETS: [&str; 7] = ["two_spheres", "final", "materials", "cornell", "checkerboard", "caustics", "instancing"];
+
+// Builds a preset with its default parameters
+pub fn by_name(name: &str, aspect_ratio: f64) -> Option<Scene> {
+    match name {
+        "two_spheres" => Some(two_spheres(aspect_ratio)),
+        "final" => Some(final_scene(aspect_ratio, 11, 0)),
+        "materials" => Some(material_row(aspect_ratio)),
+        "cornell" => Some(cornell_box(aspect_ratio)),
+        "checkerboard" => Some(checkerboard(aspect_ratio, 1.0)),
+        "caustics" => Some(glass_caustics(aspect_ratio)),
+        "instancing" => Some(instancing(aspect_ratio, 20, 0)),
+        _ => None
+    }
+}
+
+fn sky(camera: Camera, world: HittableList) -> Scene {
+    Scene { camera, world, lights: Vec::new(), background: Background::Sky }
+}
+
+fn lambertian(r: f64, g: f64, b: f64) -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new((r, g, b))))
+}
+
+// Conductor that reflects the color head on. The book's fuzzy metal with fuzz f
+// looks about like roughness sqrt(f), which the presets below were picked by.
+fn metal(r: f64, g: f64, b: f64, roughness: f64) -> Rc<dyn Material> {
+    Rc::new(Conductor::from_reflectance(Color::new((r, g, b)), roughness))
+}
+
+fn sphere(center: (f64, f64, f64), radius: f64, material: Rc<dyn Material>) -> Rc<dyn Hittable> {
+    Rc::new(SphereData::new(Point3::new(center), radius, material))
+}
+
+fn quad(q: (f64, f64, f64), u: (f64, f64, f64), v: (f64, f64, f64), material: Rc<dyn Material>) -> Rc<dyn Hittable> {
+    Rc::new(QuadData::new(Point3::new(q), Vec3::new(u), Vec3::new(v), material))
+}
+
+fn camera(lookfrom: (f64, f64, f64), lookat: (f64, f64, f64), vfov: f64, aspect_ratio: f64) -> Camera {
+    Camera::new(Point3::new(lookfrom), Point3::new(lookat), Vec3::new((0.0, 1.0, 0.0)), vfov, aspect_ratio)
+}
+
+// Blue and green diffuse spheres touching in front of the camera, the default render
+pub fn two_spheres(aspect_ratio: f64) -> Scene {
+    let r = (std::f64::consts::PI / 4.0).cos();
+    let mut world = HittableList::new();
+    world.add(sphere((-r, 0.0, -1.0), r, lambertian(0.0, 0.0, 1.0)));
+    world.add(sphere((r, 0.0, -1.0), r, lambertian(0.0, 1.0, 0.0)));
+    sky(camera((-2.0, 2.0, 1.0), (0.0, 0.0, -1.0), 90.0, aspect_ratio), world)
+}
+
+// Cover image of Ray Tracing in One Weekend: three large spheres among small
+// random ones on a (2 * extent)^2 grid, laid out from seed
+pub fn final_scene(aspect_ratio: f64, extent: i32, seed: u64) -> Scene {
+    let mut rng = random::seeded(seed);
+    let mut world = HittableList::new();
+    world.add(sphere((0.0, -1000.0, 0.0), 1000.0, lambertian(0.5, 0.5, 0.5)));
+
+    let glass: Rc<dyn Material> = Rc::new(Dielectric::new(1.5));
+    for a in -extent..extent {
+        for b in -extent..extent {
+            let center = Point3::new((a as f64 + 0.9 * rng.gen_range(0.0, 1.0), 0.2, b as f64 + 0.9 * rng.gen_range(0.0, 1.0)));
+            if (center - Point3::new((4.0, 0.2, 0.0))).length() <= 0.9 {
+                continue;
+            }
+            let choose_mat: f64 = rng.gen_range(0.0, 1.0);
+            let material: Rc<dyn Material> = if choose_mat < 0.8 {
+                let mut channel = || rng.gen_range(0.0, 1.0) * rng.gen_range(0.0, 1.0);
+                lambertian(channel(), channel(), channel())
+            } else if choose_mat < 0.95 {
+                let mut channel = || rng.gen_range(0.5, 1.0);
+                let (r, g, b) = (channel(), channel(), channel());
+                metal(r, g, b, rng.gen_range(0.0, 0.7))
+            } else {
+                glass.clone()
+            };
+            world.add(Rc::new(SphereData::new(center, 0.2, material)));
+        }
+    }
+
+    world.add(sphere((0.0, 1.0, 0.0), 1.0, glass));
+    world.add(sphere((-4.0, 1.0, 0.0), 1.0, lambertian(0.4, 0.2, 0.1)));
+    world.add(sphere((4.0, 1.0, 0.0), 1.0, metal(0.7, 0.6, 0.5, 0.0)));
+    sky(camera((13.0, 2.0, 3.0), (0.0, 0.0, 0.0), 20.0, aspect_ratio), world)
+}
+
+// Diffuse, hollow glass and rough metal spheres side by side on a yellow ground
+pub fn material_row(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+    world.add(sphere((0.0, -100.5, -1.0), 100.0, lambertian(0.8, 0.8, 0.0)));
+    world.add(sphere((0.0, 0.0, -1.0), 0.5, lambertian(0.1, 0.2, 0.5)));
+    world.add(sphere((1.0, 0.0, -1.0), 0.5, metal(0.8, 0.6, 0.2, 0.55)));
+    world.add(sphere((-1.0, 0.0, -1.0), 0.5, Rc::new(Dielectric::new(1.5))));
+    world.add(sphere((-1.0, 0.0, -1.0), -0.45, Rc::new(Dielectric::new(1.5))));
+    sky(camera((0.0, 0.6, 2.0), (0.0, 0.0, -1.0), 40.0, aspect_ratio), world)
+}
+
+// The classic 555 unit box lit only by the panel in its ceiling
+pub fn cornell_box(aspect_ratio: f64) -> Scene {
+    let red = lambertian(0.65, 0.05, 0.05);
+    let white = lambertian(0.73, 0.73, 0.73);
+    let green = lambertian(0.12, 0.45, 0.15);
+    let light: Rc<dyn Material> = Rc::new(DiffuseLight::new(Color::new((15.0, 15.0, 15.0))));
+
+    let mut world = HittableList::new();
+    world.add(quad((555.0, 0.0, 0.0), (0.0, 0.0, 555.0), (0.0, 555.0, 0.0), green));
+    world.add(quad((0.0, 0.0, 555.0), (0.0, 0.0, -555.0), (0.0, 555.0, 0.0), red));
+    world.add(quad((0.0, 0.0, 0.0), (555.0, 0.0, 0.0), (0.0, 0.0, 555.0), white.clone()));
+    world.add(quad((0.0, 555.0, 555.0), (555.0, 0.0, 0.0), (0.0, 0.0, -555.0), white.clone()));
+    world.add(quad((555.0, 0.0, 555.0), (-555.0, 0.0, 0.0), (0.0, 555.0, 0.0), white.clone()));
+    // facing down into the box
+    world.add(quad((343.0, 554.0, 332.0), (-130.0, 0.0, 0.0), (0.0, 0.0, -105.0), light));
+
+    let tall = Rc::new(cuboid(Point3::new((0.0, 0.0, 0.0)), Point3::new((165.0, 330.0, 165.0)), white.clone()));
+    world.add(Rc::new(InstanceData::new(tall, Vec3::new((265.0, 0.0, 295.0)), 15.0, 1.0)));
+    let short = Rc::new(cuboid(Point3::new((0.0, 0.0, 0.0)), Point3::new((165.0, 165.0, 165.0)), white));
+    world.add(Rc::new(InstanceData::new(short, Vec3::new((130.0, 0.0, 65.0)), -18.0, 1.0)));
+
+    Scene {
+        camera: camera((278.0, 278.0, -800.0), (278.0, 278.0, 0.0), 40.0, aspect_ratio),
+        world,
+        lights: Vec::new(),
+        background: Background::Solid(Color::new((0.0, 0.0, 0.0)))
+    }
+}
+
+// Spheres of assorted materials on a checkered ground under sky and sun,
+// cells_per_unit sets the size of the checks
+pub fn checkerboard(aspect_ratio: f64, cells_per_unit: f64) -> Scene {
+    let constant = |r: f64, g: f64, b: f64| -> Rc<dyn Texture> {Rc::new(SolidColor::new(Color::new((r, g, b))))};
+    let checker: Rc<dyn Texture> = Rc::new(CheckerTexture::new(constant(0.2, 0.3, 0.1), constant(0.9, 0.9, 0.9), cells_per_unit));
+
+    let mut world = HittableList::new();
+    world.add(sphere((0.0, -1000.0, 0.0), 1000.0, Rc::new(Lambertian::new_textured(checker))));
+    world.add(sphere((-2.2, 1.0, 0.0), 1.0, lambertian(0.7, 0.3, 0.3)));
+    world.add(sphere((0.0, 1.0, 0.0), 1.0, Rc::new(Dielectric::new(1.5))));
+    world.add(sphere((2.2, 1.0, 0.0), 1.0, Rc::new(Conductor::gold(0.2))));
+
+    let sun: Rc<dyn Light> = Rc::new(DirectionalLight::new(Vec3::new((-1.0, -2.0, -0.5)), Color::new((1.5, 1.4, 1.2))));
+    Scene {
+        camera: camera((0.0, 3.0, 9.0), (0.0, 0.8, 0.0), 35.0, aspect_ratio),
+        world,
+        lights: vec![sun],
+        background: Background::Sky
+    }
+}
+
+// Glass, diamond and hollow glass spheres focusing a small bright lamp onto a
+// white floor in the dark. The caustics are found by paths alone and need
+// many samples to converge.
+pub fn glass_caustics(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+    world.add(quad((-6.0, 0.0, 6.0), (12.0, 0.0, 0.0), (0.0, 0.0, -12.0), lambertian(0.8, 0.8, 0.8)));
+    world.add(sphere((-1.6, 0.7, 0.0), 0.7, Rc::new(Dielectric::crown_glass())));
+    world.add(sphere((0.0, 0.7, 0.0), 0.7, Rc::new(Dielectric::diamond())));
+    world.add(sphere((1.6, 0.7, 0.0), 0.7, Rc::new(Dielectric::new(1.5))));
+    world.add(sphere((1.6, 0.7, 0.0), -0.6, Rc::new(Dielectric::new(1.5))));
+    world.add(sphere((0.5, 4.0, -1.5), 0.4, Rc::new(DiffuseLight::new(Color::new((40.0, 38.0, 34.0))))));
+
+    Scene {
+        camera: camera((0.0, 3.0, 6.0), (0.0, 0.5, 0.0), 40.0, aspect_ratio),
+        world,
+        lights: Vec::new(),
+        background: Background::Solid(Color::new((0.02, 0.02, 0.03)))
+    }
+}
+
+// per_side^2 instances of one shared object, each turned and scaled at random
+// from seed, to stress traversal of many transformed copies
+pub fn instancing(aspect_ratio: f64, per_side: i32, seed: u64) -> Scene {
+    let mut rng = random::seeded(seed);
+    let mut template = HittableList::new();
+    template.add(Rc::new(cuboid(Point3::new((-0.5, 0.0, -0.5)), Point3::new((0.5, 0.6, 0.5)), lambertian(0.6, 0.6, 0.65))));
+    template.add(sphere((0.0, 0.9, 0.0), 0.3, metal(0.9, 0.7, 0.4, 0.3)));
+    template.add(sphere((0.35, 0.75, 0.35), 0.15, Rc::new(Dielectric::new(1.5))));
+    let template: Rc<dyn Hittable> = Rc::new(template);
+
+    let mut world = HittableList::new();
+    world.add(sphere((0.0, -1000.0, 0.0), 1000.0, lambertian(0.5, 0.5, 0.5)));
+    let spacing = 1.5;
+    let start = -(per_side - 1) as f64 * spacing / 2.0;
+    for i in 0..per_side {
+        for j in 0..per_side {
+            let offset = Vec3::new((start + i as f64 * spacing, 0.0, start + j as f64 * spacing));
+            let angle = rng.gen_range(0.0, 360.0);
+            let scale = rng.gen_range(0.5, 1.0);
+            world.add(Rc::new(InstanceData::new(template.clone(), offset, angle, scale)));
+        }
+    }
+
+    let distance = per_side.max(2) as f64 * spacing;
+    sky(camera((distance * 0.6, distance * 0.5, distance * 0.8), (0.0, 0.0, 0.0), 40.0, aspect_ratio), world)
+}
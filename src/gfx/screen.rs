@@ -1,60 +1,128 @@
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
-
-use crate::basic_types::vec3::Color;
-use crate::basic_types::vec3::Vec3Traits;
-use crate::basic_types::vec3::ColorTraits;
-
-pub struct Screen {
-    color_array: Vec<Vec<Color>>,
-    image_width: i32,
-    image_height: i32
-}
-
-pub trait ScreenTraits {
-    fn new(color_array: Vec<Vec<Color>>, image_width: i32, image_height: i32) -> Self;
-    fn empty_screen(image_width: i32, image_height: i32) -> Self;
-    fn color_pixel(&mut self, x: usize, y: usize, rgb_val: Color, samples_per_pixel: i32);
-    fn write_to_ppm(&self, filename: String);
-}
-
-impl ScreenTraits for Screen {
-    fn new(color_array: Vec<Vec<Color>>  , image_width: i32, image_height: i32) -> Self {
-        Screen {
-            color_array: color_array,
-            image_width: image_width,
-            image_height: image_height
-        }
-    }
-
-    fn empty_screen(image_width: i32, image_height: i32) -> Self {
-        let color_array = vec![vec![Color::new((0.0, 0.0, 0.0)); image_height as usize]; image_width as usize];
-        Screen {
-            color_array,
-            image_width,
-            image_height
-        }
-
-    }
-
-    fn color_pixel(&mut self, x: usize, y: usize, rgb_val: Color, samples_per_pixel: i32) {
-        let scale = 1.0 / samples_per_pixel as f64;
-        self.color_array[x][y] = (scale * rgb_val).sqrt();
-    }
-
-    fn write_to_ppm(&self, filename: String) {
-        let path = format!("{}.ppm", filename);
-        let path = Path::new(&path);
-        let mut file = File::create(path).unwrap();
-        writeln!(&mut file, "P3").unwrap();
-        writeln!(&mut file, "{} {}", self.image_width, self.image_height).unwrap();
-        writeln!(&mut file, "255").unwrap();
-        for j in (0..self.image_height).rev() {
-            for i in 0..self.image_width {
-                writeln!(&mut file, "{}", self.color_array[i as usize][j as usize].write_color()).unwrap();
-            }
-        }
-    
-    }
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+use crate::basic_types::vec3::ColorTraits;
+
+use crate::gfx::denoise;
+use crate::gfx::denoise::DenoiseSettings;
+use crate::gfx::denoise::FeatureBuffers;
+
+use crate::gfx::tile::Tile;
+use crate::gfx::tile::TileTraits;
+
+#[derive(Clone)]
+pub struct Screen {
+    color_array: Vec<Vec<Color>>,
+    image_width: i32,
+    image_height: i32
+}
+
+pub trait ScreenTraits {
+    fn new(color_array: Vec<Vec<Color>>, image_width: i32, image_height: i32) -> Self;
+    fn empty_screen(image_width: i32, image_height: i32) -> Self;
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    fn pixel(&self, x: usize, y: usize) -> Color;
+    fn color_pixel(&mut self, x: usize, y: usize, rgb_val: Color, samples_per_pixel: i32);
+    // Copies region over from other, which is the same size, to merge a cropped render into a full image
+    fn paste(&mut self, other: &Screen, region: Tile);
+    fn denoise(&self, features: &FeatureBuffers, settings: &DenoiseSettings) -> Self;
+    fn write_to_ppm(&self, filename: String);
+    // 8-bit RGB PNG of the image, clamped the same way as the PPM output
+    fn to_png(&self) -> Vec<u8>;
+}
+
+impl ScreenTraits for Screen {
+    fn new(color_array: Vec<Vec<Color>>  , image_width: i32, image_height: i32) -> Self {
+        Screen {
+            color_array,
+            image_width,
+            image_height
+        }
+    }
+
+    fn empty_screen(image_width: i32, image_height: i32) -> Self {
+        let color_array = vec![vec![Color::new((0.0, 0.0, 0.0)); image_height as usize]; image_width as usize];
+        Screen {
+            color_array,
+            image_width,
+            image_height
+        }
+
+    }
+
+    fn width(&self) -> i32 {
+        self.image_width
+    }
+
+    fn height(&self) -> i32 {
+        self.image_height
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Color {
+        self.color_array[x][y]
+    }
+
+    fn color_pixel(&mut self, x: usize, y: usize, rgb_val: Color, samples_per_pixel: i32) {
+        let scale = 1.0 / samples_per_pixel as f64;
+        self.color_array[x][y] = (scale * rgb_val).sqrt();
+    }
+
+    fn paste(&mut self, other: &Screen, region: Tile) {
+        let region = region.intersection(&Tile::image(self.image_width, self.image_height))
+            .intersection(&Tile::image(other.image_width, other.image_height));
+        for (i, j) in region.coordinates() {
+            self.color_array[i as usize][j as usize] = other.color_array[i as usize][j as usize];
+        }
+    }
+
+    fn denoise(&self, features: &FeatureBuffers, settings: &DenoiseSettings) -> Self {
+        // stored pixels are gamma 2 encoded, the filter works on linear values
+        let linear: Vec<Vec<Color>> = self.color_array.iter()
+            .map(|column| column.iter().map(|c| *c * *c).collect())
+            .collect();
+        let filtered = denoise::atrous(&linear, features, settings);
+        let color_array = filtered.iter()
+            .map(|column| column.iter().map(|c| c.sqrt()).collect())
+            .collect();
+        Screen::new(color_array, self.image_width, self.image_height)
+    }
+
+    fn write_to_ppm(&self, filename: String) {
+        let path = format!("{}.ppm", filename);
+        let path = Path::new(&path);
+        let mut file = File::create(path).unwrap();
+        writeln!(&mut file, "P3").unwrap();
+        writeln!(&mut file, "{} {}", self.image_width, self.image_height).unwrap();
+        writeln!(&mut file, "255").unwrap();
+        for j in (0..self.image_height).rev() {
+            for i in 0..self.image_width {
+                writeln!(&mut file, "{}", self.color_array[i as usize][j as usize].write_color()).unwrap();
+            }
+        }
+    
+    }
+
+    fn to_png(&self) -> Vec<u8> {
+        let byte = |v: f64| (256.0 * v.clamp(0.0, 0.999)) as u8;
+        let mut data = Vec::with_capacity((3 * self.image_width * self.image_height) as usize);
+        for j in (0..self.image_height).rev() {
+            for i in 0..self.image_width {
+                let c = self.color_array[i as usize][j as usize];
+                data.extend_from_slice(&[byte(c.x()), byte(c.y()), byte(c.z())]);
+            }
+        }
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.image_width as u32, self.image_height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        // writing into memory can't fail
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
 }
\ No newline at end of file
@@ -0,0 +1,320 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::gfx::progress;
+
+use crate::gfx::renderer::ActiveTimer;
+use crate::gfx::renderer::ActiveTimerTraits;
+use crate::gfx::renderer::Progress;
+use crate::gfx::renderer::ProgressTraits;
+use crate::gfx::renderer::RenderControl;
+use crate::gfx::renderer::RenderControlTraits;
+
+use crate::gfx::screen::Screen;
+use crate::gfx::screen::ScreenTraits;
+
+use crate::gfx::stats::RenderStatsTraits;
+
+// Longest request head read before giving up on a client
+const MAX_REQUEST: usize = 8192;
+// Each client has a thread of its own, so a slow one only holds up itself
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+// Clients answered at once, the ones past it are turned away with a 503
+pub const MAX_CLIENTS: usize = 8;
+// The accept loop turns clients away itself, so it waits on them only this long
+const BUSY_TIMEOUT: Duration = Duration::from_millis(100);
+
+// How often the page polls for new progress and a new image, in milliseconds
+const REFRESH_MS: u32 = 1000;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>rust_tracer</title>
+<style>
+body { background: #222; color: #ddd; font-family: sans-serif; margin: 2em; }
+img { image-rendering: pixelated; max-width: 100%; border: 1px solid #444; }
+button { margin-right: 0.5em; }
+pre { font-size: 0.9em; }
+</style>
+</head>
+<body>
+<img id="image" src="/image.png">
+<p>
+<button onclick="send('pause')">Pause</button>
+<button onclick="send('resume')">Resume</button>
+<button onclick="send('cancel')">Cancel</button>
+</p>
+<pre id="status"></pre>
+<script>
+let version = -1;
+function send(action) {
+    fetch('/' + action, { method: 'POST' }).then(refresh);
+}
+function refresh() {
+    fetch('/progress').then(r => r.json()).then(p => {
+        document.getElementById('status').textContent = JSON.stringify(p, null, 2);
+        if (p.image_version !== version) {
+            version = p.image_version;
+            document.getElementById('image').src = '/image.png?v=' + version;
+        }
+    }).catch(() => {
+        document.getElementById('status').textContent = 'render finished, server stopped';
+    });
+}
+refresh();
+setInterval(refresh, REFRESH_MS);
+</script>
+</body>
+</html>
+"#;
+
+// What the render thread last published
+struct SharedState {
+    png: Vec<u8>,
+    // bumped with every new image so the page only reloads when it changed
+    image_version: u64,
+    progress: Option<Progress>,
+    // render time without pauses
+    timer: ActiveTimer,
+    finished: bool
+}
+
+// Serves the image of a running render and its progress on localhost. The
+// render thread publishes into it, requests are answered on threads of their own,
+// up to MAX_CLIENTS of them.
+//   GET  /              page that refreshes itself
+//   GET  /image.png     latest image
+//   GET  /progress      progress and statistics as JSON
+//   POST /pause, /resume, /cancel
+// Requests must name the server as Host, 127.0.0.1:<port> or localhost:<port>,
+// so pages on other sites can't reach it through DNS rebinding. POSTs must also
+// come from the server's own page, by Origin.
+pub struct PreviewServer {
+    address: SocketAddr,
+    state: Arc<Mutex<SharedState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+pub trait PreviewServerTraits {
+    // Listens on 127.0.0.1:port, port 0 picks a free one
+    fn start(port: u16, control: RenderControl) -> io::Result<Self> where Self: Sized;
+    fn address(&self) -> SocketAddr;
+    fn url(&self) -> String;
+    fn publish_image(&self, screen: &Screen);
+    fn publish_progress(&self, progress: &Progress);
+    // Marks the render as done, the last image and progress stay available
+    fn finish(&self);
+}
+
+impl PreviewServerTraits for PreviewServer {
+    fn start(port: u16, control: RenderControl) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(SharedState {
+            png: Vec::new(),
+            image_version: 0,
+            progress: None,
+            timer: control.timer(),
+            finished: false
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (state, stop) = (state.clone(), stop.clone());
+            // handlers still running, only this loop adds to it
+            let clients = Arc::new(AtomicUsize::new(0));
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        if clients.load(Ordering::SeqCst) >= MAX_CLIENTS {
+                            let _ = turn_away(stream);
+                            continue;
+                        }
+                        clients.fetch_add(1, Ordering::SeqCst);
+                        let (state, control, clients) = (state.clone(), control.clone(), clients.clone());
+                        thread::spawn(move || {
+                            // a client that goes away mid request is no concern of the render
+                            let _ = handle(stream, &state, &control, address.port());
+                            clients.fetch_sub(1, Ordering::SeqCst);
+                        });
+                    }
+                }
+            })
+        };
+        Ok(PreviewServer { address, state, stop, thread: Some(thread) })
+    }
+
+    fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}/", self.address)
+    }
+
+    fn publish_image(&self, screen: &Screen) {
+        let png = screen.to_png();
+        let mut state = self.state.lock().unwrap();
+        state.png = png;
+        state.image_version += 1;
+    }
+
+    fn publish_progress(&self, progress: &Progress) {
+        self.state.lock().unwrap().progress = Some(*progress);
+    }
+
+    fn finish(&self) {
+        self.state.lock().unwrap().finished = true;
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // wake the accept loop so it sees the flag, clients still being
+        // answered finish on their own threads
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<SharedState>, control: &RenderControl, port: u16) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let head = read_head(&mut stream)?;
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let target = request_line.next().unwrap_or("");
+    let path = target.split('?').next().unwrap_or("");
+
+    let hosts = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    let ours = |host: &str| hosts.iter().any(|ours| ours.eq_ignore_ascii_case(host));
+    if !header(&head, "Host").is_some_and(ours) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", b"unknown host\n");
+    }
+    if method == "POST" && !header(&head, "Origin").and_then(|origin| origin.strip_prefix("http://")).is_some_and(ours) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", b"cross origin request\n");
+    }
+
+    match (method, path) {
+        ("GET", "/") => respond(&mut stream, "200 OK", "text/html; charset=utf-8",
+            PAGE.replace("REFRESH_MS", &REFRESH_MS.to_string()).as_bytes()),
+        ("GET", "/image.png") => {
+            let png = state.lock().unwrap().png.clone();
+            if png.is_empty() {
+                respond(&mut stream, "503 Service Unavailable", "text/plain", b"no image yet\n")
+            } else {
+                respond(&mut stream, "200 OK", "image/png", &png)
+            }
+        }
+        ("GET", "/progress") => {
+            let json = progress_json(&state.lock().unwrap(), control);
+            respond(&mut stream, "200 OK", "application/json", json.as_bytes())
+        }
+        ("POST", "/pause") | ("POST", "/resume") | ("POST", "/cancel") => {
+            match path {
+                "/pause" => control.pause(),
+                "/resume" => control.resume(),
+                _ => control.cancel()
+            }
+            let json = progress_json(&state.lock().unwrap(), control);
+            respond(&mut stream, "200 OK", "application/json", json.as_bytes())
+        }
+        (_, "/") | (_, "/image.png") | (_, "/progress") | (_, "/pause") | (_, "/resume") | (_, "/cancel") =>
+            respond(&mut stream, "405 Method Not Allowed", "text/plain", b"method not allowed\n"),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"not found\n")
+    }
+}
+
+fn turn_away(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(BUSY_TIMEOUT))?;
+    stream.set_write_timeout(Some(BUSY_TIMEOUT))?;
+    // closing with the request unread would reset the connection before the client reads the answer
+    read_head(&mut stream)?;
+    respond(&mut stream, "503 Service Unavailable", "text/plain", b"too many clients\n")
+}
+
+// Request line and headers, up to the blank line. Bodies are never needed.
+fn read_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..n]);
+        if head.len() > MAX_REQUEST {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request head too long"));
+        }
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+// Value of the first header called name, names are case insensitive
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+fn progress_json(state: &SharedState, control: &RenderControl) -> String {
+    let status = if control.is_cancelled() {
+        "cancelled"
+    } else if state.finished {
+        "finished"
+    } else if control.is_paused() {
+        "paused"
+    } else {
+        "rendering"
+    };
+    let elapsed = state.timer.elapsed();
+    let mut fields = vec![
+        format!("\"status\": \"{}\"", status),
+        format!("\"image_version\": {}", state.image_version),
+        format!("\"elapsed_seconds\": {:.3}", elapsed.as_secs_f64())
+    ];
+    if let Some(p) = state.progress {
+        let fraction = p.fraction();
+        let eta = progress::eta(elapsed, fraction).map_or("null".to_string(), |eta| format!("{:.3}", eta.as_secs_f64()));
+        let stats = p.stats;
+        fields.push(format!("\"percent\": {:.2}", 100.0 * fraction));
+        fields.push(format!("\"eta_seconds\": {}", eta));
+        fields.push(format!("\"pixels_done\": {}, \"pixels\": {}", p.pixels_done, p.pixels));
+        fields.push(format!("\"passes_done\": {}, \"passes\": {}", p.passes_done, p.passes));
+        fields.push(format!("\"rays_per_second\": {:.0}", stats.total_rays() as f64 / elapsed.as_secs_f64().max(1e-9)));
+        fields.push(format!("\"stats\": {{\"total_rays\": {}, \"primary_rays\": {}, \"secondary_rays\": {}, \"shadow_rays\": {}, \
+            \"average_depth\": {:.3}, \"intersection_tests\": {}, \"box_tests\": {}}}",
+            stats.total_rays(), stats.primary_rays, stats.secondary_rays, stats.shadow_rays,
+            stats.average_depth(), stats.intersection_tests, stats.box_tests));
+    }
+    format!("{{{}}}\n", fields.join(", "))
+}
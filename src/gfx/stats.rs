@@ -0,0 +1,199 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops;
+use std::time::Duration;
+use std::time::Instant;
+
+// Work counters, kept per thread so the hot paths only bump a local
+struct Counters {
+    camera_rays: Cell<u64>,
+    rays: Cell<u64>,
+    shadow_rays: Cell<u64>,
+    intersection_tests: Cell<u64>,
+    box_tests: Cell<u64>
+}
+
+thread_local! {
+    static COUNTERS: Counters = const {
+        Counters {
+            camera_rays: Cell::new(0),
+            rays: Cell::new(0),
+            shadow_rays: Cell::new(0),
+            intersection_tests: Cell::new(0),
+            box_tests: Cell::new(0)
+        }
+    };
+}
+
+fn bump(counter: fn(&Counters) -> &Cell<u64>) {
+    COUNTERS.with(|counters| {
+        let cell = counter(counters);
+        cell.set(cell.get() + 1);
+    });
+}
+
+// A path starting at the camera
+pub fn count_camera_ray() {
+    bump(|counters| &counters.camera_rays)
+}
+
+// Any path segment traced against the world, the camera ray included
+pub fn count_ray() {
+    bump(|counters| &counters.rays)
+}
+
+// A visibility test towards a light
+pub fn count_shadow_ray() {
+    bump(|counters| &counters.shadow_rays)
+}
+
+// A ray tested against a single primitive
+pub fn count_intersection_test() {
+    bump(|counters| &counters.intersection_tests)
+}
+
+// A ray tested against a bounding box
+pub fn count_box_test() {
+    bump(|counters| &counters.box_tests)
+}
+
+// Totals counted on this thread so far, subtract an earlier snapshot with
+// since to get the work done in between
+pub fn snapshot() -> RenderStats {
+    COUNTERS.with(|counters| RenderStats {
+        primary_rays: counters.camera_rays.get(),
+        secondary_rays: counters.rays.get().saturating_sub(counters.camera_rays.get()),
+        shadow_rays: counters.shadow_rays.get(),
+        intersection_tests: counters.intersection_tests.get(),
+        box_tests: counters.box_tests.get()
+    })
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct RenderStats {
+    pub primary_rays: u64,
+    // bounces and medium scattering after the camera ray
+    pub secondary_rays: u64,
+    pub shadow_rays: u64,
+    pub intersection_tests: u64,
+    pub box_tests: u64
+}
+
+pub trait RenderStatsTraits {
+    fn since(&self, earlier: &RenderStats) -> RenderStats;
+    fn total_rays(&self) -> u64;
+    // Segments per camera path, a path ending on its first hit has depth 1
+    fn average_depth(&self) -> f64;
+}
+
+impl RenderStatsTraits for RenderStats {
+    fn since(&self, earlier: &RenderStats) -> RenderStats {
+        RenderStats {
+            primary_rays: self.primary_rays - earlier.primary_rays,
+            secondary_rays: self.secondary_rays - earlier.secondary_rays,
+            shadow_rays: self.shadow_rays - earlier.shadow_rays,
+            intersection_tests: self.intersection_tests - earlier.intersection_tests,
+            box_tests: self.box_tests - earlier.box_tests
+        }
+    }
+
+    fn total_rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    fn average_depth(&self) -> f64 {
+        if self.primary_rays == 0 {
+            return 0.0;
+        }
+        (self.primary_rays + self.secondary_rays) as f64 / self.primary_rays as f64
+    }
+}
+
+impl ops::Add for RenderStats {
+    type Output = RenderStats;
+
+    fn add(self, other: RenderStats) -> RenderStats {
+        RenderStats {
+            primary_rays: self.primary_rays + other.primary_rays,
+            secondary_rays: self.secondary_rays + other.secondary_rays,
+            shadow_rays: self.shadow_rays + other.shadow_rays,
+            intersection_tests: self.intersection_tests + other.intersection_tests,
+            box_tests: self.box_tests + other.box_tests
+        }
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rays           {} total, {} primary, {} secondary, {} shadow", si(self.total_rays() as f64),
+            si(self.primary_rays as f64), si(self.secondary_rays as f64), si(self.shadow_rays as f64))?;
+        writeln!(f, "path depth     {:.2} average", self.average_depth())?;
+        write!(f, "intersections  {} primitive, {} bounding box", si(self.intersection_tests as f64), si(self.box_tests as f64))
+    }
+}
+
+// Wall clock time of each step of a run, in the order they happened
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimes {
+    phases: Vec<(&'static str, Duration)>
+}
+
+pub trait PhaseTimesTraits {
+    fn new() -> Self;
+    fn time<T, F: FnOnce() -> T>(&mut self, name: &'static str, f: F) -> T;
+    fn get(&self, name: &str) -> Option<Duration>;
+    fn total(&self) -> Duration;
+}
+
+impl PhaseTimesTraits for PhaseTimes {
+    fn new() -> Self {
+        PhaseTimes::default()
+    }
+
+    fn time<T, F: FnOnce() -> T>(&mut self, name: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.phases.push((name, start.elapsed()));
+        result
+    }
+
+    fn get(&self, name: &str) -> Option<Duration> {
+        self.phases.iter().find(|(phase, _)| *phase == name).map(|(_, time)| *time)
+    }
+
+    fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, time)| *time).sum()
+    }
+}
+
+impl fmt::Display for PhaseTimes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let phases: Vec<String> = self.phases.iter().map(|(name, time)| format!("{} {}", name, duration(*time))).collect();
+        write!(f, "time           {}, {} total", phases.join(", "), duration(self.total()))
+    }
+}
+
+// 1234567 as 1.23M
+pub fn si(value: f64) -> String {
+    let units = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")];
+    match units.iter().find(|(scale, _)| value.abs() >= *scale) {
+        Some((scale, unit)) => format!("{:.2}{}", value / scale, unit),
+        None => format!("{:.0}", value)
+    }
+}
+
+// Durations to the precision worth reading, 850ms, 4.2s, 3m05s, 1h02m
+pub fn duration(d: Duration) -> String {
+    let seconds = d.as_secs_f64();
+    if seconds < 1.0 {
+        format!("{}ms", d.as_millis())
+    } else if seconds < 60.0 {
+        // truncated like the units below, rounding would print 59.96s as 60.0s
+        let tenths = d.as_millis() / 100;
+        format!("{}.{}s", tenths / 10, tenths % 10)
+    } else if seconds < 3600.0 {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    } else {
+        format!("{}h{:02}m", d.as_secs() / 3600, d.as_secs() / 60 % 60)
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt::Write as FmtWrite;
+use std::io::IsTerminal;
+use std::io::Write;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::screen::Screen;
+use crate::gfx::screen::ScreenTraits;
+
+// Terminal size assumed when it can't be found out
+const DEFAULT_COLUMNS: usize = 80;
+const DEFAULT_LINES: usize = 24;
+// Lines kept free under the preview for the progress line and the shell prompt
+const RESERVED_LINES: usize = 2;
+
+// Upper half block, drawn with the top pixel as foreground and the bottom one as background
+const HALF_BLOCK: char = '\u{2580}';
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorSupport {
+    // 24-bit escapes, exact colors
+    TrueColor,
+    // the 6x6x6 cube of 256 color terminals
+    Ansi256
+}
+
+pub trait ColorSupportTraits {
+    // What stderr can show going by the usual environment variables, or why
+    // it can't show a preview at all
+    fn detect() -> Result<Self, String> where Self: Sized;
+}
+
+impl ColorSupportTraits for ColorSupport {
+    fn detect() -> Result<Self, String> {
+        if !std::io::stderr().is_terminal() {
+            return Err("stderr is not a terminal".to_string());
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term.is_empty() || term == "dumb" {
+            return Err("the terminal type is unknown or dumb".to_string());
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Ok(ColorSupport::TrueColor)
+        } else if term.contains("256color") {
+            Ok(ColorSupport::Ansi256)
+        } else {
+            Err(format!("{} supports neither 24-bit nor 256 colors", term))
+        }
+    }
+}
+
+// Columns and lines of the terminal on stderr, from COLUMNS and LINES if
+// set, then `stty size`, then a standard 80x24
+pub fn terminal_size() -> (usize, usize) {
+    let from_env = |name: &str| std::env::var(name).ok().and_then(|value| value.parse::<usize>().ok());
+    if let (Some(columns), Some(lines)) = (from_env("COLUMNS"), from_env("LINES")) {
+        return (columns, lines);
+    }
+    let stty = std::fs::File::open("/dev/tty").ok().and_then(|tty| {
+        std::process::Command::new("stty").arg("size").stdin(tty).output().ok()
+    });
+    if let Some(output) = stty {
+        let text = String::from_utf8_lossy(&output.stdout);
+        let sizes: Vec<usize> = text.split_whitespace().filter_map(|value| value.parse().ok()).collect();
+        if let [lines, columns] = sizes[..] {
+            return (columns, lines);
+        }
+    }
+    (DEFAULT_COLUMNS, DEFAULT_LINES)
+}
+
+// Downsampled view of a Screen drawn on stderr with half block characters,
+// two pixels per character cell
+pub struct TerminalPreview {
+    colors: ColorSupport,
+    columns: usize,
+    lines: usize,
+    // redraw over the last preview rather than below it
+    in_place: bool,
+    drawn: bool
+}
+
+pub trait TerminalPreviewTraits {
+    // Largest preview of an image_width x image_height image that fits in
+    // columns x lines, keeping the image's aspect ratio
+    fn new(colors: ColorSupport, columns: usize, lines: usize, image_width: i32, image_height: i32, in_place: bool) -> Self;
+    // A preview sized to the current terminal, or why there can't be one
+    fn detect(image_width: i32, image_height: i32, in_place: bool) -> Result<Self, String> where Self: Sized;
+    fn columns(&self) -> usize;
+    fn lines(&self) -> usize;
+    fn draw(&mut self, screen: &Screen);
+    // Escape sequences for one full preview, each line ending in a newline
+    fn frame(&self, screen: &Screen) -> String;
+}
+
+impl TerminalPreview {
+    // Average of the screen pixels under half cell (column, row), row 0 at the top
+    fn cell_color(&self, screen: &Screen, column: usize, row: usize) -> Color {
+        let (width, height) = (screen.width().max(0) as usize, screen.height().max(0) as usize);
+        let rows = 2 * self.lines;
+        let x0 = column * width / self.columns;
+        let x1 = ((column + 1) * width / self.columns).max(x0 + 1).min(width);
+        let y0 = row * height / rows;
+        let y1 = ((row + 1) * height / rows).max(y0 + 1).min(height);
+        let mut total = Color::new((0.0, 0.0, 0.0));
+        let mut count = 0;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                // screen rows count up from the bottom
+                total += screen.pixel(x, height - 1 - y);
+                count += 1;
+            }
+        }
+        if count == 0 {total} else {total / count as f64}
+    }
+
+    // Foreground (38) or background (48) color escape
+    fn escape(&self, layer: i32, color: Color) -> String {
+        let byte = |v: f64| (256.0 * v.clamp(0.0, 0.999)) as i32;
+        let (r, g, b) = (byte(color.x()), byte(color.y()), byte(color.z()));
+        match self.colors {
+            ColorSupport::TrueColor => format!("\x1b[{};2;{};{};{}m", layer, r, g, b),
+            ColorSupport::Ansi256 => {
+                let level = |v: i32| v * 6 / 256;
+                format!("\x1b[{};5;{}m", layer, 16 + 36 * level(r) + 6 * level(g) + level(b))
+            }
+        }
+    }
+}
+
+impl TerminalPreviewTraits for TerminalPreview {
+    fn new(colors: ColorSupport, columns: usize, lines: usize, image_width: i32, image_height: i32, in_place: bool) -> Self {
+        let (width, height) = (image_width.max(1) as f64, image_height.max(1) as f64);
+        let max_lines = lines.saturating_sub(RESERVED_LINES).max(1);
+        let mut columns = columns.min(image_width.max(1) as usize).max(1);
+        if (columns as f64 * height / width / 2.0).ceil() as usize > max_lines {
+            columns = ((max_lines as f64 * 2.0 * width / height) as usize).max(1);
+        }
+        let lines = ((columns as f64 * height / width / 2.0).ceil() as usize).max(1);
+        TerminalPreview { colors, columns, lines, in_place, drawn: false }
+    }
+
+    fn detect(image_width: i32, image_height: i32, in_place: bool) -> Result<Self, String> {
+        let colors = ColorSupport::detect()?;
+        let (columns, lines) = terminal_size();
+        Ok(TerminalPreview::new(colors, columns, lines, image_width, image_height, in_place))
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+
+    fn lines(&self) -> usize {
+        self.lines
+    }
+
+    fn draw(&mut self, screen: &Screen) {
+        let mut out = String::new();
+        // clear whatever the progress line left on the current line first
+        out.push_str("\r\x1b[2K");
+        if self.in_place && self.drawn {
+            let _ = write!(out, "\x1b[{}A", self.lines);
+        }
+        out.push_str(&self.frame(screen));
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(out.as_bytes());
+        let _ = stderr.flush();
+        self.drawn = true;
+    }
+
+    fn frame(&self, screen: &Screen) -> String {
+        let mut out = String::new();
+        for line in 0..self.lines {
+            for column in 0..self.columns {
+                let top = self.cell_color(screen, column, 2 * line);
+                let bottom = self.cell_color(screen, column, 2 * line + 1);
+                out.push_str(&self.escape(38, top));
+                out.push_str(&self.escape(48, bottom));
+                out.push(HALF_BLOCK);
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+}
@@ -0,0 +1,36 @@
+use std::rc::Rc;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::texture::Texture;
+
+// 3D checker pattern, scale is the number of cells per world unit
+pub struct CheckerTexture {
+    odd: Rc<dyn Texture>,
+    even: Rc<dyn Texture>,
+    scale: f64
+}
+
+pub trait CheckerTextureFn : Texture {
+    fn new(odd: Rc<dyn Texture>, even: Rc<dyn Texture>, scale: f64) -> Self;
+}
+
+impl CheckerTextureFn for CheckerTexture {
+    fn new(odd: Rc<dyn Texture>, even: Rc<dyn Texture>, scale: f64) -> Self {
+        CheckerTexture { odd, even, scale }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        let s = std::f64::consts::PI * self.scale;
+        let sines = (s * p.x()).sin() * (s * p.y()).sin() * (s * p.z()).sin();
+        if sines < 0.0 {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::io;
+
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::texture::Texture;
+
+// Bilinearly filtered image wrapped in both directions, v = 0 is the bottom row
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // row major, top row first as stored in the file
+    data: Vec<Color>
+}
+
+pub trait ImageTextureFn : Texture {
+    fn new(width: usize, height: usize, data: Vec<Color>) -> Self;
+    // Color maps stored with the same gamma 2 encoding our renders are written with
+    fn load_color(path: &str) -> io::Result<Self> where Self: Sized;
+    // Normal, height and mask maps whose values are used as stored
+    fn load_data(path: &str) -> io::Result<Self> where Self: Sized;
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// Reads binary (P6) and ASCII (P3) PPM files into [0, 1] values
+pub(crate) fn read_ppm(path: &str) -> io::Result<(usize, usize, Vec<Color>)> {
+    let bytes = fs::read(path)?;
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 4 {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos < bytes.len() && bytes[pos] == b'#' {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("truncated PPM header"));
+        }
+        header.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+    }
+    let parse = |s: &String| s.parse::<usize>().map_err(|_| invalid("bad PPM header"));
+    let (width, height, max) = (parse(&header[1])?, parse(&header[2])?, parse(&header[3])? as f64);
+    let count = width * height * 3;
+
+    let values: Vec<f64> = match header[0].as_str() {
+        "P6" if max < 256.0 => {
+            let body = &bytes[(pos + 1).min(bytes.len())..];
+            if body.len() < count {
+                return Err(invalid("truncated PPM data"));
+            }
+            body[..count].iter().map(|b| *b as f64 / max).collect()
+        }
+        "P3" => {
+            let text = String::from_utf8_lossy(&bytes[pos..]);
+            let values: Vec<f64> = text.split_whitespace().take(count)
+                .map(|t| t.parse::<f64>().map(|v| v / max).map_err(|_| invalid("bad PPM value")))
+                .collect::<io::Result<Vec<f64>>>()?;
+            if values.len() < count {
+                return Err(invalid("truncated PPM data"));
+            }
+            values
+        }
+        _ => return Err(invalid("unsupported image format, expected P3 or P6 PPM")),
+    };
+    let data = values.chunks(3).map(|c| Color::new((c[0], c[1], c[2]))).collect();
+    Ok((width, height, data))
+}
+
+impl ImageTextureFn for ImageTexture {
+    fn new(width: usize, height: usize, data: Vec<Color>) -> Self {
+        ImageTexture { width, height, data }
+    }
+
+    fn load_color(path: &str) -> io::Result<Self> {
+        let (width, height, data) = read_ppm(path)?;
+        let data = data.iter().map(|c| *c * *c).collect();
+        Ok(ImageTexture { width, height, data })
+    }
+
+    fn load_data(path: &str) -> io::Result<Self> {
+        let (width, height, data) = read_ppm(path)?;
+        Ok(ImageTexture { width, height, data })
+    }
+}
+
+impl ImageTexture {
+    fn texel(&self, i: i64, j: i64) -> Color {
+        let i = i.rem_euclid(self.width as i64) as usize;
+        let j = j.rem_euclid(self.height as i64) as usize;
+        self.data[j * self.width + i]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        if self.data.is_empty() {
+            return Color::new((0.0, 1.0, 1.0));
+        }
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (i, j) = (x.floor(), y.floor());
+        let (fx, fy) = (x - i, y - j);
+        let (i, j) = (i as i64, j as i64);
+        (1.0 - fy) * ((1.0 - fx) * self.texel(i, j) + fx * self.texel(i + 1, j))
+            + fy * ((1.0 - fx) * self.texel(i, j + 1) + fx * self.texel(i + 1, j + 1))
+    }
+}
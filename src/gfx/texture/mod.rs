@@ -0,0 +1,16 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3Traits;
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+
+    // Scalar parameters such as roughness are stored in the first channel
+    fn scalar(&self, u: f64, v: f64, p: Point3) -> f64 {
+        self.value(u, v, p).x()
+    }
+}
+
+pub mod solid_color;
+pub mod checker;
+pub mod image;
@@ -0,0 +1,30 @@
+use crate::basic_types::vec3::Color;
+use crate::basic_types::vec3::Point3;
+use crate::basic_types::vec3::Vec3Traits;
+
+use crate::gfx::texture::Texture;
+
+pub struct SolidColor {
+    color_value: Color
+}
+
+pub trait SolidColorFn : Texture {
+    fn new(color_value: Color) -> Self;
+    fn from_scalar(value: f64) -> Self;
+}
+
+impl SolidColorFn for SolidColor {
+    fn new(color_value: Color) -> Self {
+        SolidColor { color_value }
+    }
+
+    fn from_scalar(value: f64) -> Self {
+        SolidColor { color_value: Color::new((value, value, value)) }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+        self.color_value
+    }
+}
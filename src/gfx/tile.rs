@@ -0,0 +1,172 @@
+// Rectangle of pixels [x0, x1) x [y0, y1) in Screen coordinates, where y
+// counts up from the bottom row
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32
+}
+
+pub trait TileTraits {
+    fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self;
+    // Columns [x0, x1) of rows [top, bottom) counted down from the top, the
+    // way image files and viewers number them
+    fn from_top_down(x0: i32, top: i32, x1: i32, bottom: i32, image_height: i32) -> Self;
+    // The whole of a width x height image
+    fn image(width: i32, height: i32) -> Self;
+    // Pixels in both, empty if they don't overlap
+    fn intersection(&self, other: &Tile) -> Tile;
+    fn contains(&self, i: i32, j: i32) -> bool;
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    fn pixels(&self) -> usize;
+    // Pixels in the order tile results store them, top row first
+    fn coordinates(&self) -> impl Iterator<Item = (i32, i32)>;
+}
+
+impl TileTraits for Tile {
+    fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Tile { x0, y0, x1, y1 }
+    }
+
+    fn from_top_down(x0: i32, top: i32, x1: i32, bottom: i32, image_height: i32) -> Self {
+        Tile::new(x0, image_height - bottom, x1, image_height - top)
+    }
+
+    fn image(width: i32, height: i32) -> Self {
+        Tile::new(0, 0, width, height)
+    }
+
+    fn intersection(&self, other: &Tile) -> Tile {
+        let (x0, y0) = (self.x0.max(other.x0), self.y0.max(other.y0));
+        Tile::new(x0, y0, self.x1.min(other.x1).max(x0), self.y1.min(other.y1).max(y0))
+    }
+
+    fn contains(&self, i: i32, j: i32) -> bool {
+        i >= self.x0 && i < self.x1 && j >= self.y0 && j < self.y1
+    }
+
+    fn width(&self) -> i32 {
+        (self.x1 - self.x0).max(0)
+    }
+
+    fn height(&self) -> i32 {
+        (self.y1 - self.y0).max(0)
+    }
+
+    fn pixels(&self) -> usize {
+        (self.width() * self.height()) as usize
+    }
+
+    fn coordinates(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (x0, x1) = (self.x0, self.x1);
+        (self.y0..self.y1).rev().flat_map(move |j| (x0..x1).map(move |i| (i, j)))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TileOrder {
+    // rows of tiles from the top down, each left to right
+    Scanline,
+    // outwards from the tile at the center, where the subject usually is
+    Spiral,
+    // along a Hilbert curve, so tiles done one after another are neighbours
+    Hilbert
+}
+
+pub const TILE_ORDERS: [&str; 3] = ["scanline", "spiral", "hilbert"];
+
+pub trait TileOrderTraits {
+    fn by_name(name: &str) -> Option<Self> where Self: Sized;
+}
+
+impl TileOrderTraits for TileOrder {
+    fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "scanline" => Some(TileOrder::Scanline),
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None
+        }
+    }
+}
+
+// Tiles of at most tile_width x tile_height covering region exactly once, in
+// the given order. Tiles are laid out from the top left corner of the region,
+// so the ones on its right and bottom edges may be smaller.
+pub fn schedule(region: Tile, tile_width: i32, tile_height: i32, order: TileOrder) -> Vec<Tile> {
+    let (tile_width, tile_height) = (tile_width.max(1), tile_height.max(1));
+    let columns = (region.width() + tile_width - 1) / tile_width;
+    let rows = (region.height() + tile_height - 1) / tile_height;
+    // tile in column c of row r, rows counted from the top
+    let tile = |(c, r): (i32, i32)| Tile::new(
+        region.x0 + c * tile_width,
+        (region.y1 - (r + 1) * tile_height).max(region.y0),
+        (region.x0 + (c + 1) * tile_width).min(region.x1),
+        region.y1 - r * tile_height
+    );
+    let cells = match order {
+        TileOrder::Scanline => (0..rows).flat_map(|r| (0..columns).map(move |c| (c, r))).collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => hilbert(columns, rows)
+    };
+    cells.into_iter().map(tile).collect()
+}
+
+// Cells of a columns x rows grid walked in a square spiral from its center,
+// skipping the turns that fall outside a grid that isn't square
+fn spiral(columns: i32, rows: i32) -> Vec<(i32, i32)> {
+    let count = (columns.max(0) * rows.max(0)) as usize;
+    let mut cells = Vec::with_capacity(count);
+    let (mut c, mut r) = ((columns - 1) / 2, (rows - 1) / 2);
+    // right, down, left, up
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut leg = 0;
+    while cells.len() < count {
+        // legs grow by one every second turn: 1, 1, 2, 2, 3, 3, ...
+        let (dc, dr) = directions[leg % 4];
+        for _ in 0..leg / 2 + 1 {
+            if c >= 0 && c < columns && r >= 0 && r < rows {
+                cells.push((c, r));
+            }
+            c += dc;
+            r += dr;
+        }
+        leg += 1;
+    }
+    cells.truncate(count);
+    cells
+}
+
+// Cells of a columns x rows grid in the order a Hilbert curve over the
+// smallest power of two square holding it visits them
+fn hilbert(columns: i32, rows: i32) -> Vec<(i32, i32)> {
+    let mut side = 1;
+    while side < columns.max(rows) {
+        side *= 2;
+    }
+    (0..side * side).map(|d| hilbert_cell(side, d)).filter(|&(c, r)| c < columns && r < rows).collect()
+}
+
+// Cell at distance d along the Hilbert curve filling a side x side square
+fn hilbert_cell(side: i32, d: i32) -> (i32, i32) {
+    let (mut x, mut y, mut t) = (0, 0, d);
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
@@ -1,6 +1,6 @@
-fn degrees_to_radians(degrees: f64) -> f64 {
-    return degrees * std::f64::consts::PI / 180.0;
-}
-
-pub mod basic_types;
-pub mod gfx;
+fn degrees_to_radians(degrees: f64) -> f64 {
+    degrees * std::f64::consts::PI / 180.0
+}
+
+pub mod basic_types;
+pub mod gfx;
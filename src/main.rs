@@ -1,113 +1,395 @@
-use std::rc::Rc;
+use lib::gfx::denoise::DenoiseSettings;
 
+use lib::gfx::integrator::ColorMode;
 
-use rand::Rng;
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+use lib::gfx::renderer::RenderSettingsTraits;
 
-use lib::basic_types::vec3::Color;
-use lib::basic_types::vec3::Point3;
-use lib::basic_types::vec3::Vec3Traits;
+use lib::gfx::scene;
 
-use lib::basic_types::ray::Ray;
-use lib::basic_types::ray::RayTraits;
+use lib::gfx::progress::ProgressReporter;
+use lib::gfx::progress::ProgressReporterTraits;
+use lib::gfx::progress::ReportStyle;
+use lib::gfx::progress::ReportStyleTraits;
 
+use lib::gfx::terminal::TerminalPreview;
+use lib::gfx::terminal::TerminalPreviewTraits;
+
+use lib::gfx::server::PreviewServer;
+use lib::gfx::server::PreviewServerTraits;
+
+use lib::gfx::distributed;
+use lib::gfx::distributed::Coordinator;
+use lib::gfx::distributed::CoordinatorTraits;
+use lib::gfx::distributed::Job;
+
+use lib::gfx::stats;
+use lib::gfx::stats::PhaseTimes;
+use lib::gfx::stats::PhaseTimesTraits;
+use lib::gfx::stats::RenderStatsTraits;
 
 use lib::gfx::screen::Screen;
 use lib::gfx::screen::ScreenTraits;
 
+use lib::gfx::tile;
+use lib::gfx::tile::Tile;
+use lib::gfx::tile::TileTraits;
+use lib::gfx::tile::TileOrder;
+use lib::gfx::tile::TileOrderTraits;
+
+use lib::gfx::compare;
+use lib::gfx::compare::Image;
+use lib::gfx::compare::ImageTraits;
+
+// Parses `--denoise` (edge-avoiding a-trous) or `--denoise=svgf` (variance guided)
+fn denoise_settings(args: &[String]) -> Option<DenoiseSettings> {
+    for arg in args {
+        match arg.as_str() {
+            "--denoise" | "--denoise=atrous" => return Some(DenoiseSettings::default()),
+            "--denoise=svgf" => return Some(DenoiseSettings { variance_guided: true, sigma_color: 4.0, ..DenoiseSettings::default() }),
+            _ => {}
+        }
+    }
+    None
+}
+
+// `--seed=N` picks the sequence of random numbers, renders with the same seed match
+fn render_seed(args: &[String]) -> u64 {
+    args.iter()
+        .filter_map(|arg| arg.strip_prefix("--seed="))
+        .filter_map(|value| value.parse().ok())
+        .next()
+        .unwrap_or(0)
+}
+
+// `--scene=name` picks one of the presets in lib::gfx::scene
+fn scene_name(args: &[String]) -> &str {
+    args.iter()
+        .filter_map(|arg| arg.strip_prefix("--scene="))
+        .next()
+        .unwrap_or("two_spheres")
+}
+
+// `--spectral` traces one wavelength per path instead of RGB
+fn color_mode(args: &[String]) -> ColorMode {
+    if args.iter().any(|arg| arg == "--spectral") {ColorMode::Spectral} else {ColorMode::Rgb}
+}
+
+// `--passes=N` sweeps over the image N times. `--preview` and `--serve`
+// show the image after each sweep and default to PREVIEW_PASSES of them.
+fn render_passes(args: &[String]) -> i32 {
+    let previewed = args.iter().any(|arg| arg == "--preview" || arg.starts_with("--serve"));
+    args.iter()
+        .filter_map(|arg| arg.strip_prefix("--passes="))
+        .filter_map(|value| value.parse().ok())
+        .next()
+        .unwrap_or(if previewed {PREVIEW_PASSES} else {1})
+}
+
+// `--serve` or `--serve=PORT` watches the render from a browser on localhost
+fn serve_port(args: &[String]) -> Result<Option<u16>, String> {
+    for arg in args {
+        if arg == "--serve" {
+            return Ok(Some(DEFAULT_PORT));
+        }
+        if let Some(port) = arg.strip_prefix("--serve=") {
+            return port.parse().map(Some).map_err(|_| format!("invalid port {}", port));
+        }
+    }
+    Ok(None)
+}
+
+// `--tile=N` renders each pass N x N pixels at a time, in the order picked by
+// `--order=scanline|spiral|hilbert`. Without it rows go from the top down.
+fn tiling(args: &[String]) -> Result<(Option<i32>, TileOrder), String> {
+    let size = match args.iter().filter_map(|arg| arg.strip_prefix("--tile=")).next() {
+        Some(size) => match size.parse() {
+            Ok(size) if size > 0 => Some(size),
+            _ => return Err(format!("invalid tile size {}", size))
+        },
+        None => None
+    };
+    let order = match args.iter().filter_map(|arg| arg.strip_prefix("--order=")).next() {
+        Some(name) => TileOrder::by_name(name)
+            .ok_or_else(|| format!("unknown tile order {}, expected one of {}", name, tile::TILE_ORDERS.join(", ")))?,
+        None => TileOrder::Scanline
+    };
+    Ok((size, order))
+}
+
+// `--region=x0,y0,x1,y1` only renders columns x0 to x1 of rows y0 to y1, both
+// ends exclusive and counted from the top left corner like in the image file
+fn render_region(args: &[String]) -> Result<Option<Tile>, String> {
+    let value = match args.iter().filter_map(|arg| arg.strip_prefix("--region=")).next() {
+        Some(value) => value,
+        None => return Ok(None)
+    };
+    let bounds: Result<Vec<i32>, _> = value.split(',').map(|bound| bound.trim().parse()).collect();
+    let region = match bounds.as_deref() {
+        Ok(&[x0, y0, x1, y1]) => Tile::from_top_down(x0, y0, x1, y1, IMAGE_HEIGHT),
+        _ => return Err(format!("invalid region {}, expected four integers x0,y0,x1,y1", value))
+    };
+    if region.intersection(&Tile::image(IMAGE_WIDTH, IMAGE_HEIGHT)).pixels() == 0 {
+        return Err(format!("region {} has no pixels inside the {}x{} image", value, IMAGE_WIDTH, IMAGE_HEIGHT));
+    }
+    Ok(Some(region))
+}
+
+// `--merge=path` pastes the rendered region over an earlier image of the same
+// size rather than leaving the rest of the picture black, for the denoised image too
+fn merge_target(args: &[String]) -> Result<Option<Screen>, String> {
+    let path = match args.iter().filter_map(|arg| arg.strip_prefix("--merge=")).next() {
+        Some(path) => path,
+        None => return Ok(None)
+    };
+    let image = Image::load(path).map_err(|e| format!("{}: {}", path, e))?;
+    if image.width() != IMAGE_WIDTH as usize || image.height() != IMAGE_HEIGHT as usize {
+        return Err(format!("{} is {}x{} but the render is {}x{}", path, image.width(), image.height(), IMAGE_WIDTH, IMAGE_HEIGHT));
+    }
+    Ok(Some(image.to_screen()))
+}
+
+// The render, or the earlier image with the render's region pasted in
+fn merged(screen: Screen, target: Option<Screen>, region: Tile) -> Screen {
+    match target {
+        Some(mut target) => {
+            target.paste(&screen, region);
+            target
+        }
+        None => screen
+    }
+}
+
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    })
+}
 
-use lib::gfx::hittable::HitRecordTraits;
-use lib::gfx::hittable::hittables::Hittable;
-use lib::gfx::hittable::hittables::hittable_list::HittableList;
-use lib::gfx::hittable::hittables::hittable_list::HittableListTrait;
-use lib::gfx::hittable::hittables::sphere::Sphere;
-use lib::gfx::hittable::hittables::sphere::SphereData;
+// `--progress=interactive|log|off`, by default interactive only on a terminal
+fn report_style(args: &[String]) -> Result<ReportStyle, String> {
+    match args.iter().filter_map(|arg| arg.strip_prefix("--progress=")).next() {
+        None => Ok(ReportStyle::detect()),
+        Some("interactive") => Ok(ReportStyle::Interactive),
+        Some("log") => Ok(ReportStyle::Log),
+        Some("off") => Ok(ReportStyle::Silent),
+        Some(other) => Err(format!("unknown progress style {}, expected interactive, log or off", other))
+    }
+}
 
-use lib::gfx::material::lambertian::Lambertian;
-use lib::gfx::material::lambertian::LambertianFn;
-use lib::gfx::material::metal::Metal;
-use lib::gfx::material::metal::MetalFn;
-use lib::gfx::material::dielectric::Dielectric;
-use lib::gfx::material::dielectric::DielectricFn;
+// `compare <reference> <test> [--heatmap=name]` prints error metrics of test
+// against reference and writes a false color difference to name.ppm, name may
+// carry the .ppm itself
+fn compare_images(args: &[String]) -> Result<(), String> {
+    let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    if paths.len() != 2 {
+        return Err("usage: compare <reference> <test> [--heatmap=name]".to_string());
+    }
+    let load = |path: &String| Image::load(path).map_err(|e| format!("{}: {}", path, e));
+    let (reference, test) = (load(paths[0])?, load(paths[1])?);
+    if reference.width() != test.width() || reference.height() != test.height() {
+        return Err(format!("{} is {}x{} but {} is {}x{}", paths[0], reference.width(), reference.height(),
+            paths[1], test.width(), test.height()));
+    }
+    let heatmap_name = args.iter()
+        .filter_map(|arg| arg.strip_prefix("--heatmap="))
+        .next()
+        .unwrap_or("heatmap");
+    // write_to_ppm adds the extension, so --heatmap=diff.ppm isn't written to diff.ppm.ppm
+    let heatmap_name = heatmap_name.strip_suffix(".ppm").unwrap_or(heatmap_name);
 
-use lib::gfx::camera::Camera;
-use lib::gfx::camera::CameraTraits;
+    let metrics = compare::compare(&reference, &test);
+    println!("MSE     {:.6e}", metrics.mse);
+    println!("RMSE    {:.6e}", metrics.rmse);
+    println!("PSNR    {:.2} dB", metrics.psnr);
+    println!("SSIM    {:.4}", metrics.ssim);
+    println!("relMSE  {:.6e}", metrics.rel_mse);
 
-fn ray_color(r: Ray, world: &HittableList, depth: i32) -> Color {
-    if depth <= 0 {
-        return Color::new((0.0,0.0,0.0));
+    let (heatmap, scale) = compare::heatmap(&reference, &test);
+    heatmap.write_to_ppm(heatmap_name.to_string());
+    if scale > 0.0 {
+        println!("heatmap {}.ppm, red is a relative squared error of {:.3e}", heatmap_name, scale);
+    } else {
+        println!("heatmap {}.ppm, the images are identical", heatmap_name);
     }
-    let (rec, flag) = world.hit(r, 0.001, std::f64::INFINITY);
-    
-    if flag  {
-        let rec = rec.unwrap();
-        let (attenuation, scattered, flag) = rec.mat_ptr().scatter(&r, &rec);
-        if flag {
-            return attenuation * ray_color(scattered, world, depth - 1);
+    Ok(())
+}
+
+// `coordinate [--listen=address] [--timeout=seconds]` waits for workers, hands
+// them tiles of the render picked by the usual scene, sampling, tiling and region
+// flags and writes the merged image. Workers build the scene preset themselves,
+// by name. A worker that spends longer than the timeout on a tile loses it.
+fn coordinate(args: &[String]) -> Result<(), String> {
+    let listen = args.iter().filter_map(|arg| arg.strip_prefix("--listen=")).next().unwrap_or(DEFAULT_LISTEN);
+    let timeout = match args.iter().filter_map(|arg| arg.strip_prefix("--timeout=")).next() {
+        Some(seconds) => match seconds.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Some(std::time::Duration::from_secs_f64(seconds)),
+            _ => return Err(format!("invalid timeout {}, expected a number of seconds", seconds))
+        },
+        None => None
+    };
+    let (tile_size, tile_order) = tiling(args)?;
+    let (region, target) = (render_region(args)?, merge_target(args)?);
+    let job = Job {
+        scene: scene_name(args).to_string(),
+        aspect_ratio: ASPECT_RATIO,
+        settings: RenderSettings {
+            width: IMAGE_WIDTH,
+            height: IMAGE_HEIGHT,
+            seed: render_seed(args),
+            region,
+            tile_size,
+            tile_order,
+            mode: color_mode(args),
+            ..RenderSettings::default()
         }
-        return Color::new((0.0, 0.0, 0.0));
+    };
+    let region = job.settings.region();
+    let mut coordinator = Coordinator::bind(listen, job).map_err(|e| format!("cannot coordinate on {}: {}", listen, e))?;
+    if let Some(timeout) = timeout {
+        coordinator = coordinator.with_timeout(timeout);
     }
-    // Normalize ray direction 
-    let unit_direction = r.direction().unitize();
-    // transform y coordinate to viewport coordinate system then scale to range [0,1]
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * Color::new((1.0, 1.0, 1.0)) + 
-        t*Color::new((0.5, 0.7, 1.0))
+    let address = coordinator.address().map_err(|e| e.to_string())?;
+    eprintln!("waiting for workers, start them with `worker --connect={}`", address);
+
+    let start = std::time::Instant::now();
+    let output = coordinator.run(|done, total| eprintln!("tiles {}/{}", done, total)).map_err(|e| e.to_string())?;
+    merged(output.screen, target, region).write_to_ppm("image".to_string());
+    eprintln!("{}", output.stats);
+    eprintln!("workers        {}, {} tiles reassigned, {} total", output.workers, output.reassigned, stats::duration(start.elapsed()));
+    Ok(())
+}
+
+// `worker --connect=address` renders tiles for a coordinator until it has them all
+fn work(args: &[String]) -> Result<(), String> {
+    let address = args.iter().filter_map(|arg| arg.strip_prefix("--connect=")).next()
+        .ok_or_else(|| "usage: worker --connect=host:port".to_string())?;
+    let tiles = distributed::run_worker(address).map_err(|e| format!("worker for {} stopped: {}", address, e))?;
+    eprintln!("rendered {} tiles", tiles);
+    Ok(())
 }
 
+// Modes other than a plain render, picked by the first argument
+type Subcommand = fn(&[String]) -> Result<(), String>;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+const IMAGE_WIDTH: i32 = 384;
+const IMAGE_HEIGHT: i32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as i32;
+
+const PREVIEW_PASSES: i32 = 8;
+const DEFAULT_PORT: u16 = 8000;
+const DEFAULT_LISTEN: &str = "127.0.0.1:7000";
+
 fn main() {
-    const ASPECT_RATIO: f64 = 16.0 / 9.0;
-    const IMAGE_WIDTH: i32 = 384;
-    const IMAGE_HEIGHT: i32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as i32;
-    const SAMPLES_PER_PIXEL: i32 = 100;
-    const MAX_DEPTH: i32 = 50;
-
-    
-    // let mut world: HittableList = HittableList::new();
-    // let sp = SphereData::new(Point3::new((0.0, 0.0, -1.0)), 0.5, 
-    // Rc::new(Lambertian::new(Color::new((0.1, 0.2, 0.5)))));
-    // world.add(Rc::new(sp));
-    // let sp = SphereData::new(Point3::new((0.0, -100.5, -1.0)), 100.0,
-    //     Rc::new(Lambertian::new(Color::new((0.8, 0.8, 0.0)))));
-    // world.add(Rc::new(sp));
-    // let sp = SphereData::new(Point3::new((1.0, 0.0, -1.0)), 0.5,
-    //     Rc::new(Metal::new(Color::new((0.8, 0.6, 0.2)), 0.3)));
-    // world.add(Rc::new(sp));
-    // let sp = SphereData::new(Point3::new((-1.0, 0.0, -1.0)), 0.5,
-    //     Rc::new(Dielectric::new(1.5)));
-    // world.add(Rc::new(sp));
-    // let sp = SphereData::new(Point3::new((-1.0, 0.0, 1.0)), -0.45, Rc::new(Dielectric::new(1.5)));
-    // world.add(Rc::new(sp));
-    let mut rng = rand::thread_rng();
-
-    let cam = Camera::new(Point3::new((-2.0, 2.0, 1.0)), 
-            Point3::new((0.0, 0.0, -1.0)), 
-               Color::new((0.0, 1.0, 0.0)), 90.0, ASPECT_RATIO);
-    
-    let r = (std::f64::consts::PI / 4.0).cos();
-    // let cam = Camera::new(90.0, ASPECT_RATIO);
-    let mut s = Screen::empty_screen(IMAGE_WIDTH, IMAGE_HEIGHT);
-    let mut world: HittableList = HittableList::new();
-    world.add(Rc::new(SphereData::new(Point3::new((-r, 0.0, -1.0)), r, 
-            Rc::new(Lambertian::new(Color::new((0.0, 0.0, 1.0)))))));
-    world.add(Rc::new(SphereData::new(Point3::new((r, 0.0, -1.0)), r, 
-    Rc::new(Lambertian::new(Color::new((0.0, 1.0, 0.0)))))));
-    for j in (0..IMAGE_HEIGHT).rev() {
-        eprintln!("Scanlines Remaining: {}", j);
-        for i in 0..IMAGE_WIDTH {
-            let mut pixel_color = Color::new((0.0,0.0,0.0));
-            for _ in 0..SAMPLES_PER_PIXEL {
-                let u = (i as f64 + rng.gen_range(0.0, 1.0))/ (IMAGE_WIDTH - 1) as f64;
-                let v = (j as f64 + rng.gen_range(0.0, 1.0)) / (IMAGE_HEIGHT - 1) as f64;
-                let r = cam.get_ray(u, v);
-                pixel_color += ray_color(r, &world, MAX_DEPTH);
-            }
-            // let u = (i as f64 + rng.gen_range(0.0, 1.0))/ (IMAGE_WIDTH - 1) as f64;
-            // let v = (j as f64 + rng.gen_range(0.0, 1.0)) / (IMAGE_HEIGHT - 1) as f64;
-            // let r = cam.get_ray(u, v);
-            // let pixel_color = ray_color(r, &world);
-            s.color_pixel(i as usize, j as usize, pixel_color, SAMPLES_PER_PIXEL);
+    let args: Vec<String> = std::env::args().collect();
+    let subcommand: Option<Subcommand> = match args.get(1).map(|arg| arg.as_str()) {
+        Some("compare") => Some(compare_images),
+        Some("coordinate") => Some(coordinate),
+        Some("worker") => Some(work),
+        _ => None
+    };
+    if let Some(subcommand) = subcommand {
+        if let Err(message) = subcommand(&args[2..]) {
+            eprintln!("{}", message);
+            std::process::exit(1);
         }
+        return;
+    }
+    let (style, port) = (or_exit(report_style(&args)), or_exit(serve_port(&args)));
+    let (tile_size, tile_order) = or_exit(tiling(&args));
+    let (region, target) = (or_exit(render_region(&args)), or_exit(merge_target(&args)));
+    // the black outside a region is no part of the image to filter
+    let denoise = denoise_settings(&args).map(|settings| DenoiseSettings { region, ..settings });
+
+    let mut times = PhaseTimes::new();
+    let name = scene_name(&args);
+    let scene = match times.time("scene", || scene::by_name(name, ASPECT_RATIO)) {
+        Some(scene) => scene,
+        None => {
+            eprintln!("unknown scene {}, expected one of {}", name, scene::PRESETS.join(", "));
+            std::process::exit(1);
+        }
+    };
+    let settings = RenderSettings {
+        width: IMAGE_WIDTH,
+        height: IMAGE_HEIGHT,
+        seed: render_seed(&args),
+        passes: render_passes(&args),
+        region,
+        tile_size,
+        tile_order,
+        mode: color_mode(&args),
+        features: denoise.is_some(),
+        ..RenderSettings::default()
+    };
+    let mut preview = None;
+    if args.iter().any(|arg| arg == "--preview") {
+        // log lines scroll the terminal, so only redraw in place over a single progress line
+        match TerminalPreview::detect(IMAGE_WIDTH, IMAGE_HEIGHT, style != ReportStyle::Log) {
+            Ok(terminal) => preview = Some(terminal),
+            Err(reason) => eprintln!("no terminal preview, {}", reason)
+        }
+    }
+    // declared ahead of the renderer, whose callbacks borrow them
+    let server;
+    let mut reporter = ProgressReporter::new(style);
+    let mut renderer = Renderer::new(&scene, settings);
+    server = port.map(|port| match PreviewServer::start(port, renderer.control()) {
+        Ok(server) => {
+            eprintln!("watch the render at {}", server.url());
+            server
+        }
+        Err(e) => {
+            eprintln!("cannot serve on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    });
+    renderer.on_progress(|progress| {
+        reporter.report(&progress);
+        if let Some(server) = &server {
+            server.publish_progress(&progress);
+        }
+    });
+    renderer.on_pass(|screen, _| {
+        if let Some(preview) = preview.as_mut() {
+            preview.draw(screen);
+        }
+        if let Some(server) = &server {
+            server.publish_image(screen);
+        }
+    });
+    let output = times.time("render", || renderer.render());
+    drop(renderer);
+    reporter.finish();
+    if let Some(server) = &server {
+        server.publish_image(&output.screen);
+        server.finish();
+    }
+    if output.cancelled {
+        eprintln!("render cancelled, writing what was rendered so far");
+    }
+
+    let denoised = match (denoise, &output.features) {
+        (Some(settings), Some(features)) => Some(times.time("denoise", || output.screen.denoise(features, &settings))),
+        _ => None
+    };
+    let (screen, region) = (output.screen, settings.region());
+    times.time("output", || {
+        let denoised = denoised.map(|denoised| merged(denoised, target.clone(), region));
+        merged(screen, target, region).write_to_ppm("image".to_string());
+        if let Some(denoised) = denoised {
+            denoised.write_to_ppm("image_denoised".to_string());
+        }
+    });
+
+    if style != ReportStyle::Silent {
+        eprintln!("{}", output.stats);
+        // over the time spent rendering, the render phase below also counts pauses
+        let rays_per_second = output.stats.total_rays() as f64 / output.elapsed.as_secs_f64().max(1e-9);
+        eprintln!("throughput     {} rays/s", stats::si(rays_per_second));
+        eprintln!("{}", times);
     }
-    
-    s.write_to_ppm("image".to_string());
 }
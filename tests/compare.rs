@@ -0,0 +1,145 @@
+// Known answers for the image loaders and the error metrics of `compare`
+use std::fs;
+use std::path::PathBuf;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::compare;
+use lib::gfx::compare::Image;
+use lib::gfx::compare::ImageTraits;
+
+use lib::gfx::screen::ScreenTraits;
+
+// A file in the temp directory that no other test run uses
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rust_tracer_compare_{}_{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load(path: &PathBuf) -> Image {
+    let image = Image::load(path.to_str().unwrap()).unwrap();
+    fs::remove_file(path).unwrap();
+    image
+}
+
+fn uniform(width: usize, height: usize, color: Color) -> Image {
+    Image::new(width, height, vec![color; width * height])
+}
+
+fn assert_color(c: Color, expected: (f64, f64, f64)) {
+    assert!((c.x() - expected.0).abs() < 1e-12 && (c.y() - expected.1).abs() < 1e-12 && (c.z() - expected.2).abs() < 1e-12,
+        "{:?} != {:?}", c, expected);
+}
+
+#[test]
+fn loads_ascii_and_binary_ppm() {
+    let ascii = load(&temp_file("ascii.ppm", b"P3\n# comment\n2 1\n255\n255 0 0\n0 255 51\n"));
+    let mut binary = b"P6 2 1 255\n".to_vec();
+    binary.extend_from_slice(&[255, 0, 0, 0, 255, 51]);
+    let binary = load(&temp_file("binary.ppm", &binary));
+    for image in [ascii, binary] {
+        assert_eq!((image.width(), image.height()), (2, 1));
+        // gamma 2 encoded like our renders, so 51 / 255 = 0.2 is 0.04 linear
+        assert_color(image.pixel(0, 0), (1.0, 0.0, 0.0));
+        assert_color(image.pixel(1, 0), (0.0, 1.0, 0.04));
+    }
+}
+
+// 8-bit RGB PNG of the given rows, top row first as in the file
+fn png(width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(data).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+#[test]
+fn loads_png_top_row_first() {
+    let image = load(&temp_file("image.png", &png(1, 2, &[255, 0, 0, 0, 0, 0])));
+    assert_eq!((image.width(), image.height()), (1, 2));
+    assert_color(image.pixel(0, 0), (1.0, 0.0, 0.0));
+    assert_color(image.pixel(0, 1), (0.0, 0.0, 0.0));
+}
+
+#[test]
+fn loads_flat_and_run_length_encoded_hdr() {
+    // (128, 64, 32) with exponent 129 is (1, 0.5, 0.25) in linear radiance
+    let mut flat = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+    flat.extend_from_slice(&[128, 64, 32, 129, 0, 0, 0, 0]);
+    let flat = load(&temp_file("flat.hdr", &flat));
+    assert_eq!((flat.width(), flat.height()), (2, 1));
+    assert_color(flat.pixel(0, 0), (1.0, 0.5, 0.25));
+    assert_color(flat.pixel(1, 0), (0.0, 0.0, 0.0));
+
+    // 8 wide scanline, every plane a single run
+    let mut rle = b"#?RADIANCE\n\n-Y 1 +X 8\n".to_vec();
+    rle.extend_from_slice(&[2, 2, 0, 8]);
+    for value in [128, 64, 32, 129] {
+        rle.extend_from_slice(&[128 + 8, value]);
+    }
+    let rle = load(&temp_file("rle.hdr", &rle));
+    assert_eq!((rle.width(), rle.height()), (8, 1));
+    assert!((0..8).all(|x| rle.pixel(x, 0) == Color::new((1.0, 0.5, 0.25))));
+}
+
+#[test]
+fn rejects_unknown_hdr_layouts() {
+    let path = temp_file("flipped.hdr", b"#?RADIANCE\n\n+Y 1 +X 1\n\x80\x80\x80\x81");
+    assert!(Image::load(path.to_str().unwrap()).is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn identical_images_have_no_error() {
+    let image = Image::new(16, 16, (0..256).map(|i| Color::new((i as f64 / 255.0, 0.5, 0.1))).collect());
+    let metrics = compare::compare(&image, &image);
+    assert_eq!((metrics.mse, metrics.rmse, metrics.rel_mse), (0.0, 0.0, 0.0));
+    assert_eq!(metrics.psnr, f64::INFINITY);
+    assert!((metrics.ssim - 1.0).abs() < 1e-12, "SSIM {}", metrics.ssim);
+    assert_eq!(compare::heatmap(&image, &image).1, 0.0);
+}
+
+#[test]
+fn constant_offset_has_the_expected_error() {
+    let reference = uniform(8, 8, Color::new((0.5, 0.5, 0.5)));
+    let test = uniform(8, 8, Color::new((0.6, 0.6, 0.6)));
+    let metrics = compare::compare(&reference, &test);
+    assert!((metrics.mse - 0.01).abs() < 1e-12);
+    assert!((metrics.rmse - 0.1).abs() < 1e-12);
+    // 10 log10(1 / 0.01)
+    assert!((metrics.psnr - 20.0).abs() < 1e-9);
+    // the squared difference over the squared reference plus 0.01
+    assert!((metrics.rel_mse - 0.01 / 0.26).abs() < 1e-12);
+    // no structure to lose, only the brightness differs
+    assert!(metrics.ssim < 1.0 && metrics.ssim > 0.9, "SSIM {}", metrics.ssim);
+}
+
+#[test]
+fn ssim_drops_with_structure() {
+    let checker = Image::new(8, 8, (0..64).map(|i| if (i % 8 + i / 8) % 2 == 0 {Color::new((1.0, 1.0, 1.0))} else {Color::new((0.0, 0.0, 0.0))}).collect());
+    let gray = uniform(8, 8, Color::new((0.25, 0.25, 0.25)));
+    assert!(compare::ssim(&checker, &gray) < 0.1);
+}
+
+#[test]
+fn heatmap_survives_nan_pixels() {
+    let reference = uniform(4, 4, Color::new((0.5, 0.5, 0.5)));
+    let mut data = vec![Color::new((0.5, 0.5, 0.5)); 16];
+    data[0] = Color::new((f64::NAN, 0.5, 0.5));
+    data[5] = Color::new((0.6, 0.5, 0.5));
+    let (heatmap, scale) = compare::heatmap(&reference, &Image::new(4, 4, data));
+    assert!(scale.is_finite() && scale > 0.0);
+    // the NaN pixel, top left, is drawn at full scale like the largest finite error
+    assert_eq!(heatmap.pixel(0, 3), heatmap.pixel(1, 2));
+
+    let mut data = vec![Color::new((0.5, 0.5, 0.5)); 16];
+    data[3] = Color::new((0.5, f64::NAN, 0.5));
+    let (_, scale) = compare::heatmap(&reference, &Image::new(4, 4, data));
+    assert_eq!(scale, f64::INFINITY, "images differing only in a NaN are not identical");
+}
@@ -0,0 +1,162 @@
+// The denoisers on synthetic images: noise in a flat region is smoothed away
+// while an edge in the normal or depth buffer stays where it is
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::denoise::DenoiseSettings;
+use lib::gfx::denoise::FeatureBuffers;
+use lib::gfx::denoise::FeatureBuffersTraits;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::tile::Tile;
+use lib::gfx::tile::TileTraits;
+
+const SIZE: i32 = 32;
+const SAMPLES: i32 = 8;
+// radiance of a sample is its pixel's level plus or minus up to this
+const NOISE: f64 = 0.2;
+
+fn atrous() -> DenoiseSettings {
+    DenoiseSettings::default()
+}
+
+fn svgf() -> DenoiseSettings {
+    DenoiseSettings { variance_guided: true, sigma_color: 4.0, ..DenoiseSettings::default() }
+}
+
+// Noisy render of a gray image whose left and right halves have the given
+// linear levels, normals and depths
+fn render(levels: (f64, f64), normals: (Vec3, Vec3), depths: (f64, f64)) -> (Screen, FeatureBuffers) {
+    let mut rng = SmallRng::from_seed([17; 16]);
+    let mut screen = Screen::empty_screen(SIZE, SIZE);
+    let mut features = FeatureBuffers::new(SIZE, SIZE);
+    let albedo = Color::new((0.5, 0.5, 0.5));
+    for x in 0..SIZE as usize {
+        let left = x < SIZE as usize / 2;
+        let (level, normal, depth) = if left {(levels.0, normals.0, depths.0)} else {(levels.1, normals.1, depths.1)};
+        for y in 0..SIZE as usize {
+            let mut sum = Color::new((0.0, 0.0, 0.0));
+            for _ in 0..SAMPLES {
+                let v = level + rng.gen_range(-NOISE, NOISE);
+                let radiance = Color::new((v, v, v));
+                features.add_sample(x, y, radiance, albedo, normal, depth);
+                sum += radiance;
+            }
+            screen.color_pixel(x, y, sum, SAMPLES);
+        }
+    }
+    (screen, features)
+}
+
+// Linear level of a pixel, the screen holds gamma 2 encoded values
+fn level(screen: &Screen, x: usize, y: usize) -> f64 {
+    let c = screen.pixel(x, y);
+    c.x() * c.x()
+}
+
+// Variance of the linear level over columns [x0, x1) of every row
+fn variance(screen: &Screen, x0: usize, x1: usize) -> f64 {
+    let values: Vec<f64> = (x0..x1).flat_map(|x| (0..SIZE as usize).map(move |y| (x, y))).map(|(x, y)| level(screen, x, y)).collect();
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64
+}
+
+// Mean linear level of column x
+fn column(screen: &Screen, x: usize) -> f64 {
+    (0..SIZE as usize).map(|y| level(screen, x, y)).sum::<f64>() / SIZE as f64
+}
+
+fn smooths_a_flat_region(settings: &DenoiseSettings) {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    let (noisy, features) = render((0.4, 0.4), (up, up), (2.0, 2.0));
+    let denoised = noisy.denoise(&features, settings);
+    let (before, after) = (variance(&noisy, 0, SIZE as usize), variance(&denoised, 0, SIZE as usize));
+    assert!(after < 0.1 * before, "variance went from {:.3e} to {:.3e}", before, after);
+    assert!((column(&denoised, 8) - 0.4).abs() < 0.02, "level drifted to {}", column(&denoised, 8));
+}
+
+// Levels only 0.1 apart, so the color weight alone would blur across the edge
+fn keeps_the_edge(settings: &DenoiseSettings, normals: (Vec3, Vec3), depths: (f64, f64)) {
+    let levels = (0.35, 0.45);
+    let (noisy, features) = render(levels, normals, depths);
+    let denoised = noisy.denoise(&features, settings);
+    let edge = SIZE as usize / 2;
+    let (left, right) = (column(&denoised, edge - 1), column(&denoised, edge));
+    assert!((left - levels.0).abs() < 0.02 && (right - levels.1).abs() < 0.02,
+        "the edge blurred to {:.3} | {:.3}", left, right);
+
+    // the same image without the edge in its guides is blurred across it
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    let (noisy, features) = render(levels, (up, up), (depths.0, depths.0));
+    let blurred = noisy.denoise(&features, settings);
+    assert!(column(&blurred, edge) - column(&blurred, edge - 1) < right - left);
+}
+
+#[test]
+fn atrous_smooths_a_flat_region() {
+    smooths_a_flat_region(&atrous());
+}
+
+#[test]
+fn svgf_smooths_a_flat_region() {
+    smooths_a_flat_region(&svgf());
+}
+
+#[test]
+fn atrous_keeps_normal_edges() {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    keeps_the_edge(&atrous(), (up, Vec3::new((1.0, 0.0, 0.0))), (2.0, 2.0));
+}
+
+#[test]
+fn svgf_keeps_normal_edges() {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    keeps_the_edge(&svgf(), (up, Vec3::new((1.0, 0.0, 0.0))), (2.0, 2.0));
+}
+
+#[test]
+fn atrous_keeps_depth_edges() {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    keeps_the_edge(&atrous(), (up, up), (2.0, 6.0));
+}
+
+#[test]
+fn svgf_keeps_depth_edges() {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    keeps_the_edge(&svgf(), (up, up), (2.0, 6.0));
+}
+
+#[test]
+fn filters_only_inside_the_region() {
+    let up = Vec3::new((0.0, 0.0, 1.0));
+    let (noisy, features) = render((0.4, 0.4), (up, up), (2.0, 2.0));
+    let region = Tile::new(8, 0, 24, SIZE);
+    let settings = DenoiseSettings { region: Some(region), ..atrous() };
+    let denoised = noisy.denoise(&features, &settings);
+    assert!(variance(&denoised, 8, 24) < 0.1 * variance(&noisy, 8, 24));
+
+    // a render of the region alone leaves the rest black, which must not bleed in
+    let mut cropped = noisy.clone();
+    for x in (0..8).chain(24..SIZE as usize) {
+        for y in 0..SIZE as usize {
+            cropped.color_pixel(x, y, Color::new((0.0, 0.0, 0.0)), 1);
+        }
+    }
+    let cropped = cropped.denoise(&features, &settings);
+    for x in 0..SIZE as usize {
+        for y in 0..SIZE as usize {
+            if region.contains(x as i32, y as i32) {
+                assert_eq!(level(&cropped, x, y), level(&denoised, x, y), "black bled into ({}, {})", x, y);
+            } else {
+                assert_eq!(level(&denoised, x, y), level(&noisy, x, y), "({}, {}) outside was filtered", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// Coordinator and workers on localhost. Pixels are seeded on their own, so
+// however the tiles are shared out the result matches a local render exactly.
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::distributed;
+use lib::gfx::distributed::Coordinator;
+use lib::gfx::distributed::CoordinatorTraits;
+use lib::gfx::distributed::DistributedOutput;
+use lib::gfx::distributed::Job;
+
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+
+use lib::gfx::scene;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::tile::Tile;
+use lib::gfx::tile::TileTraits;
+use lib::gfx::tile::TileOrder;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+
+fn job(tile_size: i32) -> Job {
+    let settings = RenderSettings { width: 32, height: 18, samples_per_pixel: 4, max_depth: 8, seed: 11, tile_size: Some(tile_size),
+        ..RenderSettings::default() };
+    Job { scene: "materials".to_string(), aspect_ratio: ASPECT_RATIO, settings }
+}
+
+fn local_render(settings: RenderSettings) -> Screen {
+    let job = job(1);
+    let scene = scene::by_name(&job.scene, job.aspect_ratio).unwrap();
+    let output = Renderer::new(&scene, settings).render();
+    output.screen
+}
+
+fn assert_same(a: &Screen, b: &Screen) {
+    for j in 0..a.height() as usize {
+        for i in 0..a.width() as usize {
+            let (p, q) = (a.pixel(i, j), b.pixel(i, j));
+            assert!((p.x(), p.y(), p.z()) == (q.x(), q.y(), q.z()), "pixel ({}, {}) differs: {} vs {}", i, j, p, q);
+        }
+    }
+}
+
+// Runs the coordinator on a thread of its own, returning its address
+fn start(coordinator: Coordinator) -> (SocketAddr, thread::JoinHandle<DistributedOutput>) {
+    let address = coordinator.address().unwrap();
+    (address, thread::spawn(move || coordinator.run(|_, _| {}).unwrap()))
+}
+
+fn spawn_worker(address: SocketAddr) -> thread::JoinHandle<usize> {
+    thread::spawn(move || distributed::run_worker(address).unwrap())
+}
+
+// Connects like a worker and takes a tile, then hands back a reader to hold on to
+// or drop and the corners of the tile
+fn take_a_tile(address: SocketAddr) -> (BufReader<TcpStream>, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(b"HELLO rust_tracer 1\n").unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert!(line.starts_with("JOB materials "));
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    let corners = line.strip_prefix("TILE ").expect("no tile").trim_end().to_string();
+    (reader, corners)
+}
+
+#[test]
+fn workers_share_the_image() {
+    let coordinator = Coordinator::bind("127.0.0.1:0", job(8)).unwrap();
+    let (address, coordinator) = start(coordinator);
+    let workers: Vec<_> = (0..3).map(|_| spawn_worker(address)).collect();
+    let output = coordinator.join().unwrap();
+    let tiles: usize = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+
+    // 4 x 3 tiles of at most 8 x 8
+    assert_eq!(tiles, 12);
+    assert_eq!((output.workers, output.reassigned), (3, 0));
+    assert_eq!(output.stats.primary_rays, 32 * 18 * 4);
+    assert_same(&output.screen, &local_render(job(8).settings));
+}
+
+#[test]
+fn tiles_of_a_dead_worker_are_reassigned() {
+    let coordinator = Coordinator::bind("127.0.0.1:0", job(8)).unwrap();
+    let (address, coordinator) = start(coordinator);
+    drop(take_a_tile(address).0);
+    let worker = spawn_worker(address);
+    let output = coordinator.join().unwrap();
+
+    assert_eq!(worker.join().unwrap(), 12);
+    assert_eq!((output.workers, output.reassigned), (2, 1));
+    assert_same(&output.screen, &local_render(job(8).settings));
+}
+
+#[test]
+fn tiles_of_a_stalled_worker_are_reassigned() {
+    let coordinator = Coordinator::bind("127.0.0.1:0", job(16)).unwrap()
+        .with_timeout(Duration::from_millis(200));
+    let (address, coordinator) = start(coordinator);
+    // never answers, but keeps the connection open until the end
+    let (stalled, _) = take_a_tile(address);
+    let worker = spawn_worker(address);
+    let output = coordinator.join().unwrap();
+    drop(stalled);
+
+    assert_eq!(worker.join().unwrap(), 4);
+    assert_eq!(output.reassigned, 1);
+    assert_same(&output.screen, &local_render(job(16).settings));
+}
+
+#[test]
+fn results_at_another_sample_count_are_rejected() {
+    let coordinator = Coordinator::bind("127.0.0.1:0", job(16)).unwrap();
+    let (address, coordinator) = start(coordinator);
+    // a worker running other settings, one sample short
+    let (mut reader, corners) = take_a_tile(address);
+    let c: Vec<i32> = corners.split(' ').map(|corner| corner.parse().unwrap()).collect();
+    let pixels = (c[2] - c[0]) * (c[3] - c[1]);
+    let stream = reader.get_mut();
+    writeln!(stream, "RESULT {} 3 0 0 0 0 0", corners).unwrap();
+    stream.write_all(&vec![0u8; pixels as usize * 24]).unwrap();
+    let worker = spawn_worker(address);
+    let output = coordinator.join().unwrap();
+
+    assert_eq!(worker.join().unwrap(), 4);
+    assert_eq!(output.reassigned, 1);
+    assert_eq!(output.stats.primary_rays, 32 * 18 * 4);
+    assert_same(&output.screen, &local_render(job(16).settings));
+}
+
+#[test]
+fn only_the_region_is_handed_out() {
+    let mut job = job(4);
+    job.settings.region = Some(Tile::new(10, 3, 22, 11));
+    job.settings.tile_order = TileOrder::Hilbert;
+    let settings = job.settings;
+    let coordinator = Coordinator::bind("127.0.0.1:0", job).unwrap();
+    let (address, coordinator) = start(coordinator);
+    let worker = spawn_worker(address);
+    let output = coordinator.join().unwrap();
+
+    // 3 x 2 tiles of 4 x 4
+    assert_eq!(worker.join().unwrap(), 6);
+    assert_eq!(output.stats.primary_rays, 12 * 8 * 4);
+    assert_same(&output.screen, &local_render(settings));
+}
+
+#[test]
+fn refuses_unknown_scenes() {
+    let job = Job { scene: "nowhere".to_string(), ..job(8) };
+    assert!(Coordinator::bind("127.0.0.1:0", job).is_err());
+}
@@ -0,0 +1,164 @@
+// Quads, cuboids, instances, bounding boxes and area lights checked against
+// hand worked hits
+use std::rc::Rc;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::basic_types::ray::Ray;
+use lib::basic_types::ray::RayTraits;
+
+use lib::gfx::hittable::HitRecordTraits;
+use lib::gfx::hittable::aabb::Aabb;
+use lib::gfx::hittable::aabb::AabbTraits;
+use lib::gfx::hittable::hittables::Hittable;
+use lib::gfx::hittable::hittables::instance::Instance;
+use lib::gfx::hittable::hittables::instance::InstanceData;
+use lib::gfx::hittable::hittables::quad;
+use lib::gfx::hittable::hittables::quad::Quad;
+use lib::gfx::hittable::hittables::quad::QuadData;
+
+use lib::gfx::material::Material;
+use lib::gfx::material::diffuse_light::DiffuseLight;
+use lib::gfx::material::diffuse_light::DiffuseLightFn;
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+
+fn gray() -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new((0.5, 0.5, 0.5))))
+}
+
+fn v(x: f64, y: f64, z: f64) -> Vec3 {
+    Vec3::new((x, y, z))
+}
+
+fn ray(origin: (f64, f64, f64), direction: (f64, f64, f64)) -> Ray {
+    Ray::new(Point3::new(origin), Vec3::new(direction))
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn quad_reports_its_uv() {
+    // 2 wide along x, 3 high along y, facing +z
+    let quad = QuadData::new(Point3::new((0.0, 0.0, 0.0)), v(2.0, 0.0, 0.0), v(0.0, 3.0, 0.0), gray());
+    let rec = quad.hit(ray((0.5, 1.5, 1.0), (0.0, 0.0, -1.0)), 0.001, f64::INFINITY).unwrap();
+    assert!((rec.t() - 1.0).abs() < 1e-12);
+    assert!((rec.u() - 0.25).abs() < 1e-12 && (rec.v() - 0.5).abs() < 1e-12);
+    assert!(rec.front());
+    assert_close(rec.normal(), v(0.0, 0.0, 1.0));
+    assert_close(rec.p(), v(0.5, 1.5, 0.0));
+
+    // a slanted parallelogram, u and v are along its edges rather than the axes
+    let slanted = QuadData::new(Point3::new((0.0, 0.0, 0.0)), v(2.0, 0.0, 0.0), v(1.0, 1.0, 0.0), gray());
+    let rec = slanted.hit(ray((1.5, 0.5, 1.0), (0.0, 0.0, -1.0)), 0.001, f64::INFINITY).unwrap();
+    assert!((rec.u() - 0.5).abs() < 1e-12 && (rec.v() - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn quad_misses_past_its_edges() {
+    let quad = QuadData::new(Point3::new((0.0, 0.0, 0.0)), v(2.0, 0.0, 0.0), v(0.0, 3.0, 0.0), gray());
+    let down = (0.0, 0.0, -1.0);
+    // the edges themselves belong to the quad
+    assert!(quad.hit(ray((2.0, 3.0, 1.0), down), 0.001, f64::INFINITY).is_some());
+    assert!(quad.hit(ray((0.0, 0.0, 1.0), down), 0.001, f64::INFINITY).is_some());
+    for (x, y) in [(2.001, 1.0), (-0.001, 1.0), (1.0, 3.001), (1.0, -0.001)] {
+        assert!(quad.hit(ray((x, y, 1.0), down), 0.001, f64::INFINITY).is_none(), "hit at ({}, {})", x, y);
+    }
+    // parallel to the plane, and the plane behind the ray
+    assert!(quad.hit(ray((1.0, 1.0, 0.0), (1.0, 0.0, 0.0)), 0.001, f64::INFINITY).is_none());
+    assert!(quad.hit(ray((1.0, 1.0, 1.0), (0.0, 0.0, 1.0)), 0.001, f64::INFINITY).is_none());
+    // inside the slanted quad's bounding rectangle but outside the quad
+    let slanted = QuadData::new(Point3::new((0.0, 0.0, 0.0)), v(2.0, 0.0, 0.0), v(1.0, 1.0, 0.0), gray());
+    assert!(slanted.hit(ray((0.2, 0.9, 1.0), down), 0.001, f64::INFINITY).is_none());
+}
+
+#[test]
+fn cuboid_faces_point_outwards() {
+    let cuboid = quad::cuboid(Point3::new((1.0, 1.0, 1.0)), Point3::new((-1.0, -1.0, -1.0)), gray());
+    let axes = [v(1.0, 0.0, 0.0), v(-1.0, 0.0, 0.0), v(0.0, 1.0, 0.0), v(0.0, -1.0, 0.0), v(0.0, 0.0, 1.0), v(0.0, 0.0, -1.0)];
+    for axis in axes {
+        // from outside towards the face on this side
+        let rec = cuboid.hit(Ray::new(3.0 * axis, -axis), 0.001, f64::INFINITY).unwrap();
+        assert!(rec.front(), "face {:?} seen from outside", axis);
+        assert_close(rec.normal(), axis);
+        assert_close(rec.p(), axis);
+        // from the center, the same face is seen from behind
+        let rec = cuboid.hit(Ray::new(v(0.0, 0.0, 0.0), axis), 0.001, f64::INFINITY).unwrap();
+        assert!(!rec.front(), "face {:?} seen from inside", axis);
+        assert_close(rec.normal(), -axis);
+    }
+}
+
+#[test]
+fn instance_moves_hits_and_box() {
+    // the unit cube scaled by 2, turned 90 degrees so +x goes to -z, then moved along +x
+    let cube = Rc::new(quad::cuboid(Point3::new((0.0, 0.0, 0.0)), Point3::new((1.0, 1.0, 1.0)), gray()));
+    let instance = InstanceData::new(cube, v(5.0, 0.0, 0.0), 90.0, 2.0);
+
+    let bbox = instance.bounding_box().unwrap();
+    assert!((bbox.min() - v(5.0, 0.0, -2.0)).length() < 1e-3, "min {:?}", bbox.min());
+    assert!((bbox.max() - v(7.0, 2.0, 0.0)).length() < 1e-3, "max {:?}", bbox.max());
+
+    // the cube's +x face now faces -z
+    let rec = instance.hit(ray((6.0, 1.0, -10.0), (0.0, 0.0, 1.0)), 0.001, f64::INFINITY).unwrap();
+    assert!((rec.t() - 8.0).abs() < 1e-9, "t {}", rec.t());
+    assert_close(rec.p(), v(6.0, 1.0, -2.0));
+    assert!(rec.front());
+    assert_close(rec.normal(), v(0.0, 0.0, -1.0));
+
+    // its top stays on top, twice as high
+    let rec = instance.hit(ray((6.0, 5.0, -1.0), (0.0, -1.0, 0.0)), 0.001, f64::INFINITY).unwrap();
+    assert_close(rec.p(), v(6.0, 2.0, -1.0));
+    assert_close(rec.normal(), v(0.0, 1.0, 0.0));
+
+    // where the untransformed cube would be there is nothing
+    assert!(instance.hit(ray((0.5, 0.5, -10.0), (0.0, 0.0, 1.0)), 0.001, f64::INFINITY).is_none());
+    assert!(!instance.occluded(ray((0.5, 0.5, -10.0), (0.0, 0.0, 1.0)), 0.001, f64::INFINITY));
+    assert!(instance.occluded(ray((6.0, 1.0, -10.0), (0.0, 0.0, 1.0)), 0.001, f64::INFINITY));
+}
+
+#[test]
+fn box_counts_grazing_rays_as_hits() {
+    let bbox = Aabb::new(Point3::new((0.0, 0.0, 0.0)), Point3::new((1.0, 1.0, 1.0)));
+    // running along the top face, boxes only cull so touching must not be a miss
+    assert!(bbox.hit(&ray((-1.0, 1.0, 0.5), (1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    // through a corner
+    assert!(bbox.hit(&ray((-1.0, -1.0, 0.5), (1.0, 1.0, 0.0)), 0.0, f64::INFINITY));
+    // parallel and just above it
+    assert!(!bbox.hit(&ray((-1.0, 1.001, 0.5), (1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    // pointing away from it
+    assert!(!bbox.hit(&ray((-1.0, 0.5, 0.5), (-1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    // limited to before it
+    assert!(!bbox.hit(&ray((-1.0, 0.5, 0.5), (1.0, 0.0, 0.0)), 0.0, 0.5));
+}
+
+#[test]
+fn box_without_thickness_is_still_hit() {
+    // flat in y, like the box of an unpadded horizontal quad
+    let flat = Aabb::new(Point3::new((0.0, 2.0, 0.0)), Point3::new((1.0, 2.0, 1.0)));
+    assert!(flat.hit(&ray((0.5, 5.0, 0.5), (0.0, -1.0, 0.0)), 0.0, f64::INFINITY));
+    assert!(flat.hit(&ray((0.0, 0.0, 0.0), (0.25, 1.0, 0.25)), 0.0, f64::INFINITY));
+    assert!(!flat.hit(&ray((1.5, 5.0, 0.5), (0.0, -1.0, 0.0)), 0.0, f64::INFINITY));
+}
+
+#[test]
+fn area_light_is_dark_from_behind() {
+    let light: Rc<dyn Material> = Rc::new(DiffuseLight::new(Color::new((4.0, 3.0, 2.0))));
+    // facing -y, like a ceiling light
+    let quad = QuadData::new(Point3::new((0.0, 1.0, 0.0)), v(1.0, 0.0, 0.0), v(0.0, 0.0, 1.0), light);
+
+    let below = quad.hit(ray((0.5, 0.0, 0.5), (0.0, 1.0, 0.0)), 0.001, f64::INFINITY).unwrap();
+    assert!(below.front());
+    assert_close(below.mat_ptr().emitted(&below), v(4.0, 3.0, 2.0));
+
+    let above = quad.hit(ray((0.5, 2.0, 0.5), (0.0, -1.0, 0.0)), 0.001, f64::INFINITY).unwrap();
+    assert!(!above.front());
+    assert_close(above.mat_ptr().emitted(&above), v(0.0, 0.0, 0.0));
+    // and absorbs everything either way
+    assert!(!below.mat_ptr().scatter(&ray((0.5, 0.0, 0.5), (0.0, 1.0, 0.0)), &below).2);
+}
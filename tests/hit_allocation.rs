@@ -0,0 +1,66 @@
+// Intersecting a scene must not touch the heap, it happens for every ray
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+use std::rc::Rc;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::basic_types::ray::Ray;
+use lib::basic_types::ray::RayTraits;
+
+use lib::gfx::hittable::hittables::Hittable;
+use lib::gfx::hittable::hittables::hittable_list::HittableList;
+use lib::gfx::hittable::hittables::hittable_list::HittableListTrait;
+use lib::gfx::hittable::hittables::sphere::Sphere;
+use lib::gfx::hittable::hittables::sphere::SphereData;
+
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+
+// Counts the allocations made by each thread, so other tests running
+// alongside don't add to it
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+fn hitting_a_list_of_spheres_allocates_nothing() {
+    let material = Rc::new(Lambertian::new(Color::new((0.5, 0.5, 0.5))));
+    let mut world = HittableList::new();
+    for i in 0..10 {
+        world.add(Rc::new(SphereData::new(Point3::new((i as f64 - 5.0, 0.0, -5.0)), 0.4, material.clone())));
+    }
+    let hit = Ray::new(Point3::new((0.0, 0.0, 0.0)), Vec3::new((0.0, 0.0, -1.0)));
+    let miss = Ray::new(Point3::new((0.0, 0.0, 0.0)), Vec3::new((0.0, 1.0, 0.0)));
+
+    let before = allocations();
+    let rec = world.hit(hit, 0.001, f64::INFINITY);
+    let missed = world.hit(miss, 0.001, f64::INFINITY);
+    assert_eq!(allocations(), before);
+    assert!(rec.is_some() && missed.is_none());
+}
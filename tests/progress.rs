@@ -0,0 +1,43 @@
+// Estimates and number formatting behind the progress and statistics lines
+use std::time::Duration;
+
+use lib::gfx::progress;
+
+use lib::gfx::stats;
+
+#[test]
+fn eta_scales_the_elapsed_time_by_the_work_left() {
+    let elapsed = Duration::from_secs(30);
+    assert_eq!(progress::eta(elapsed, 0.0), None);
+    assert_eq!(progress::eta(elapsed, -0.5), None);
+    assert_eq!(progress::eta(elapsed, 0.25), Some(Duration::from_secs(90)));
+    assert_eq!(progress::eta(elapsed, 0.5), Some(Duration::from_secs(30)));
+    assert_eq!(progress::eta(elapsed, 1.0), Some(Duration::from_secs(0)));
+    assert_eq!(progress::eta(elapsed, 1.5), Some(Duration::from_secs(0)));
+    assert_eq!(progress::eta(Duration::from_secs(0), 0.5), Some(Duration::from_secs(0)));
+}
+
+#[test]
+fn si_switches_units_at_each_thousand() {
+    assert_eq!(stats::si(0.0), "0");
+    assert_eq!(stats::si(999.0), "999");
+    assert_eq!(stats::si(1000.0), "1.00k");
+    assert_eq!(stats::si(1234567.0), "1.23M");
+    assert_eq!(stats::si(2.5e9), "2.50G");
+    assert_eq!(stats::si(7e12), "7.00T");
+    assert_eq!(stats::si(-1500.0), "-1.50k");
+}
+
+#[test]
+fn durations_switch_units_at_a_second_a_minute_and_an_hour() {
+    assert_eq!(stats::duration(Duration::from_secs(0)), "0ms");
+    assert_eq!(stats::duration(Duration::from_millis(999)), "999ms");
+    assert_eq!(stats::duration(Duration::from_millis(1000)), "1.0s");
+    assert_eq!(stats::duration(Duration::from_millis(4250)), "4.2s");
+    assert_eq!(stats::duration(Duration::from_millis(59_960)), "59.9s");
+    assert_eq!(stats::duration(Duration::from_secs(60)), "1m00s");
+    assert_eq!(stats::duration(Duration::from_secs(185)), "3m05s");
+    assert_eq!(stats::duration(Duration::from_secs(3599)), "59m59s");
+    assert_eq!(stats::duration(Duration::from_secs(3600)), "1h00m");
+    assert_eq!(stats::duration(Duration::from_secs(3720)), "1h02m");
+}
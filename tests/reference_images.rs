@@ -0,0 +1,169 @@
+// Renders small seeded scenes and compares them against the images checked in
+// under tests/references. Run with UPDATE_REFERENCES=1 to accept new output.
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::camera::Camera;
+use lib::gfx::camera::CameraTraits;
+
+use lib::gfx::hittable::hittables::hittable_list::HittableList;
+use lib::gfx::hittable::hittables::hittable_list::HittableListTrait;
+use lib::gfx::hittable::hittables::sphere::Sphere;
+use lib::gfx::hittable::hittables::sphere::SphereData;
+
+use lib::gfx::integrator::Background;
+
+use lib::gfx::material::Material;
+use lib::gfx::material::dielectric::Dielectric;
+use lib::gfx::material::dielectric::DielectricFn;
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+use lib::gfx::material::metal::Metal;
+use lib::gfx::material::metal::MetalFn;
+
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+
+use lib::gfx::scene;
+use lib::gfx::scene::Scene;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+const IMAGE_WIDTH: i32 = 64;
+const IMAGE_HEIGHT: i32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as i32;
+const SAMPLES_PER_PIXEL: i32 = 16;
+const MAX_DEPTH: i32 = 50;
+const SEED: u64 = 2020;
+
+// Largest root mean square difference allowed, in 8 bit pixel levels. Renders
+// are deterministic, this only absorbs floating point differences across platforms.
+const MAX_RMSE: f64 = 2.0;
+
+fn render(scene: &Scene) -> Screen {
+    let settings = RenderSettings {
+        width: IMAGE_WIDTH,
+        height: IMAGE_HEIGHT,
+        samples_per_pixel: SAMPLES_PER_PIXEL,
+        max_depth: MAX_DEPTH,
+        seed: SEED,
+        ..RenderSettings::default()
+    };
+    Renderer::new(scene, settings).render().screen
+}
+
+// Width, height and RGB bytes of an ASCII PPM as written by Screen::write_to_ppm
+fn read_ppm(path: &Path) -> (usize, usize, Vec<u8>) {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    let mut tokens = text.split_whitespace();
+    assert_eq!(tokens.next(), Some("P3"), "{} is not an ASCII PPM", path.display());
+    let mut next = || tokens.next().and_then(|t| t.parse::<usize>().ok()).expect("truncated PPM");
+    let (width, height, _max) = (next(), next(), next());
+    let data = (0..width * height * 3).map(|_| next() as u8).collect();
+    (width, height, data)
+}
+
+fn write_ppm(path: &Path, width: usize, height: usize, data: &[u8]) {
+    let mut text = format!("P3\n{} {}\n255\n", width, height);
+    for rgb in data.chunks(3) {
+        text += &format!("{} {} {}\n", rgb[0], rgb[1], rgb[2]);
+    }
+    fs::write(path, text).unwrap();
+}
+
+fn rmse(a: &[u8], b: &[u8]) -> f64 {
+    let sum: f64 = a.iter().zip(b).map(|(x, y)| {
+        let d = *x as f64 - *y as f64;
+        d * d
+    }).sum();
+    (sum / a.len() as f64).sqrt()
+}
+
+fn output_dir() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("reference-images");
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Renders the scene and checks it against tests/references/<name>.ppm. On a
+// mismatch the render and an amplified difference image are left next to each other.
+fn check_reference(name: &str, scene: Scene) {
+    let out = output_dir();
+    let actual_stem = out.join(format!("{}_actual", name));
+    render(&scene).write_to_ppm(actual_stem.to_str().unwrap().to_string());
+    let actual_path = actual_stem.with_extension("ppm");
+
+    let reference_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("references").join(format!("{}.ppm", name));
+    if std::env::var_os("UPDATE_REFERENCES").is_some() {
+        fs::copy(&actual_path, &reference_path).unwrap();
+        return;
+    }
+
+    let (width, height, actual) = read_ppm(&actual_path);
+    let (ref_width, ref_height, expected) = read_ppm(&reference_path);
+    assert_eq!((width, height), (ref_width, ref_height), "{}: size differs from the reference", name);
+
+    let error = rmse(&actual, &expected);
+    if error > MAX_RMSE {
+        let diff: Vec<u8> = actual.iter().zip(&expected)
+            .map(|(x, y)| ((*x as i32 - *y as i32).abs() * 4).min(255) as u8)
+            .collect();
+        let diff_path = out.join(format!("{}_diff.ppm", name));
+        write_ppm(&diff_path, width, height, &diff);
+        panic!("{}: RMSE {:.3} exceeds {}, see {} and {}", name, error, MAX_RMSE,
+            actual_path.display(), diff_path.display());
+    }
+}
+
+// One sphere of the material on a large ground sphere, as in the book's scenes
+fn material_scene(material: Rc<dyn Material>) -> Scene {
+    let camera = Camera::new(Point3::new((0.0, 0.5, 1.0)), Point3::new((0.0, 0.0, -1.0)),
+        Vec3::new((0.0, 1.0, 0.0)), 45.0, ASPECT_RATIO);
+    let mut world = HittableList::new();
+    world.add(Rc::new(SphereData::new(Point3::new((0.0, -100.5, -1.0)), 100.0,
+        Rc::new(Lambertian::new(Color::new((0.8, 0.8, 0.0)))))));
+    world.add(Rc::new(SphereData::new(Point3::new((0.0, 0.0, -1.0)), 0.5, material)));
+    Scene { camera, world, lights: Vec::new(), background: Background::Sky }
+}
+
+#[test]
+fn lambertian_sphere() {
+    check_reference("lambertian_sphere", material_scene(Rc::new(Lambertian::new(Color::new((0.1, 0.2, 0.5))))));
+}
+
+#[test]
+fn metal_sphere() {
+    check_reference("metal_sphere", material_scene(Rc::new(Metal::new(Color::new((0.8, 0.6, 0.2)), 0.3))));
+}
+
+#[test]
+fn dielectric_sphere() {
+    check_reference("dielectric_sphere", material_scene(Rc::new(Dielectric::new(1.5))));
+}
+
+// The scene main renders by default
+#[test]
+fn main_scene() {
+    check_reference("main_scene", scene::two_spheres(ASPECT_RATIO));
+}
+
+// Quads and an area light as the only light source
+#[test]
+fn cornell_box() {
+    check_reference("cornell_box", scene::cornell_box(ASPECT_RATIO));
+}
+
+// Cuboids and spheres placed by rotated and scaled instances
+#[test]
+fn instancing() {
+    check_reference("instancing", scene::instancing(ASPECT_RATIO, 4, 1));
+}
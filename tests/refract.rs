@@ -0,0 +1,29 @@
+// Refraction through a flat surface with its normal along +y. Before the fix
+// the part of the refracted ray along the normal pointed back out of the
+// surface, so glass turned light around instead of letting it through.
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn matched_indices_leave_the_ray_unchanged() {
+    let incoming = Vec3::new((1.0, -1.0, 0.5)).unitize();
+    let normal = Vec3::new((0.0, 1.0, 0.0));
+    // the old sign gave the mirror direction (x, +y, z)
+    assert_close(incoming.refract(normal, 1.0), incoming);
+}
+
+#[test]
+fn follows_snells_law_into_the_surface() {
+    let (sin_in, eta) = (0.6, 1.0 / 1.5);
+    let incoming = Vec3::new((sin_in, -(1.0 - sin_in * sin_in).sqrt(), 0.0));
+    let normal = Vec3::new((0.0, 1.0, 0.0));
+    let refracted = incoming.refract(normal, eta);
+
+    let sin_out = eta * sin_in;
+    assert_close(refracted, Vec3::new((sin_out, -(1.0 - sin_out * sin_out).sqrt(), 0.0)));
+    assert!((refracted.length() - 1.0).abs() < 1e-12);
+}
@@ -0,0 +1,215 @@
+// Progress reporting, cancellation and determinism of the library renderer
+use std::cell::RefCell;
+
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::denoise::FeatureBuffersTraits;
+
+use lib::gfx::integrator::ColorMode;
+
+use lib::gfx::renderer::Progress;
+use lib::gfx::renderer::ProgressTraits;
+use lib::gfx::renderer::RenderControlTraits;
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+
+use lib::gfx::scene;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::stats::RenderStatsTraits;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+
+fn settings() -> RenderSettings {
+    RenderSettings { width: 32, height: 18, samples_per_pixel: 4, max_depth: 8, seed: 7, ..RenderSettings::default() }
+}
+
+fn pixels(screen: &Screen) -> Vec<(f64, f64, f64)> {
+    let mut pixels = Vec::new();
+    for j in 0..screen.height() as usize {
+        for i in 0..screen.width() as usize {
+            let c = screen.pixel(i, j);
+            pixels.push((c.x(), c.y(), c.z()));
+        }
+    }
+    pixels
+}
+
+// Exact pixel values, for comparing renders that may hold NaN
+fn bits(screen: &Screen) -> Vec<[u64; 3]> {
+    pixels(screen).into_iter().map(|(r, g, b)| [r.to_bits(), g.to_bits(), b.to_bits()]).collect()
+}
+
+#[test]
+fn reports_every_row() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let reports = RefCell::new(Vec::new());
+    let mut renderer = Renderer::new(&scene, settings());
+    renderer.on_progress(|progress| reports.borrow_mut().push(progress));
+    let output = renderer.render();
+    drop(renderer);
+
+    assert!(!output.cancelled);
+    let reports = reports.into_inner();
+    assert_eq!(reports.len(), 18);
+    // by default every row is a tile of its own
+    assert!(reports.iter().enumerate().all(|(n, p)| p.pixels_done == 32 * (n + 1) && p.pixels == 32 * 18));
+    assert_eq!(reports.last().map(Progress::fraction), Some(1.0));
+}
+
+#[test]
+fn stops_when_cancelled() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let mut renderer = Renderer::new(&scene, settings());
+    let control = renderer.control();
+    let rows = RefCell::new(0);
+    let rows_seen = &rows;
+    renderer.on_progress(move |progress| {
+        *rows_seen.borrow_mut() = progress.pixels_done / 32;
+        if progress.pixels_done == 5 * 32 {
+            control.cancel();
+        }
+    });
+    let output = renderer.render();
+    drop(renderer);
+
+    assert!(output.cancelled);
+    assert_eq!(rows.into_inner(), 5);
+    // rows are rendered top first, so the bottom of the image was never reached
+    let screen = output.screen;
+    assert!(screen.pixel(0, 17).length() > 0.0);
+    assert_eq!(screen.pixel(0, 0).length(), 0.0);
+}
+
+#[test]
+fn same_seed_same_image() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let first = Renderer::new(&scene, settings()).render();
+    let second = Renderer::new(&scene, settings()).render();
+    let other = Renderer::new(&scene, RenderSettings { seed: 8, ..settings() }).render();
+    assert_eq!(pixels(&first.screen), pixels(&second.screen));
+    assert_ne!(pixels(&first.screen), pixels(&other.screen));
+}
+
+#[test]
+fn renders_a_single_row_or_column() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    for (width, height) in [(1, 1), (1, 18), (32, 1)] {
+        let output = Renderer::new(&scene, RenderSettings { width, height, ..settings() }).render();
+        let pixels = pixels(&output.screen);
+        assert_eq!(pixels.len(), (width * height) as usize);
+        // the sky is visible everywhere along the middle of the view
+        assert!(pixels.iter().all(|&(r, g, b)| r.is_finite() && g.is_finite() && b > 0.0), "{}x{}", width, height);
+    }
+}
+
+#[test]
+fn gathers_features_on_request() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    assert!(Renderer::new(&scene, settings()).render().features.is_none());
+    let features = Renderer::new(&scene, RenderSettings { features: true, ..settings() }).render().features;
+    assert!(features.is_some_and(|f| f.width() == 32 && f.height() == 18));
+}
+
+#[test]
+fn gathering_features_leaves_the_image_unchanged() {
+    for scene in [scene::two_spheres(ASPECT_RATIO), scene::glass_caustics(ASPECT_RATIO)] {
+        for mode in [ColorMode::Rgb, ColorMode::Spectral] {
+            let plain = Renderer::new(&scene, RenderSettings { mode, ..settings() }).render();
+            let guided = Renderer::new(&scene, RenderSettings { mode, features: true, ..settings() }).render();
+            assert_eq!(bits(&plain.screen), bits(&guided.screen));
+            assert_eq!(plain.stats, guided.stats);
+        }
+    }
+}
+
+#[test]
+fn counts_the_work_done() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let reports = RefCell::new(Vec::new());
+    let mut renderer = Renderer::new(&scene, settings());
+    renderer.on_progress(|progress| reports.borrow_mut().push(progress.stats));
+    let stats = renderer.render().stats;
+    drop(renderer);
+
+    assert_eq!(stats.primary_rays, 32 * 18 * 4);
+    assert!(stats.average_depth() >= 1.0 && stats.average_depth() <= 8.0);
+    assert!(stats.intersection_tests >= 2 * (stats.primary_rays + stats.secondary_rays));
+    let reports = reports.into_inner();
+    assert!(reports.windows(2).all(|pair| pair[0].total_rays() <= pair[1].total_rays()));
+    assert_eq!(reports.last(), Some(&stats));
+}
+
+#[test]
+fn passes_leave_the_image_unchanged() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let single = Renderer::new(&scene, settings()).render();
+    let passes = RefCell::new(Vec::new());
+    let mut renderer = Renderer::new(&scene, RenderSettings { passes: 3, ..settings() });
+    renderer.on_pass(|_, progress| passes.borrow_mut().push(progress.passes_done));
+    let split = renderer.render();
+    drop(renderer);
+
+    assert_eq!(passes.into_inner(), vec![1, 2, 3]);
+    let difference = pixels(&single.screen).iter().zip(pixels(&split.screen))
+        .map(|(a, b)| (a.0 - b.0).abs().max((a.1 - b.1).abs()).max((a.2 - b.2).abs()))
+        .fold(0.0, f64::max);
+    assert!(difference < 1e-9, "passes changed a pixel by {}", difference);
+}
+
+#[test]
+fn waits_while_paused() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let mut renderer = Renderer::new(&scene, settings());
+    let control = renderer.control();
+    control.pause();
+    let resumer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(control.is_paused());
+        control.resume();
+    });
+    let started = std::time::Instant::now();
+    let output = renderer.render();
+    resumer.join().unwrap();
+    assert!(!output.cancelled);
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+}
+
+#[test]
+fn pauses_are_not_render_time() {
+    let pause = std::time::Duration::from_millis(200);
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let resumer = RefCell::new(None);
+    let reports = RefCell::new(Vec::new());
+    let (resumer_seen, reports_seen) = (&resumer, &reports);
+    let mut renderer = Renderer::new(&scene, settings());
+    let control = renderer.control();
+    let pauser = control.clone();
+    renderer.on_progress(move |progress| {
+        reports_seen.borrow_mut().push(progress.elapsed);
+        if progress.pixels_done == 9 * 32 {
+            pauser.pause();
+            let control = pauser.clone();
+            *resumer_seen.borrow_mut() = Some(std::thread::spawn(move || {
+                std::thread::sleep(pause);
+                control.resume();
+            }));
+        }
+    });
+    let started = std::time::Instant::now();
+    let output = renderer.render();
+    let wall = started.elapsed();
+    drop(renderer);
+    resumer.into_inner().unwrap().join().unwrap();
+
+    assert!(wall >= pause);
+    assert!(output.elapsed + pause <= wall, "{:?} rendering in {:?}", output.elapsed, wall);
+    // the row after the pause took no longer than the pause itself
+    let reports = reports.into_inner();
+    assert!(reports.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(reports[9] - reports[8] < pause, "{:?}", reports);
+    assert!(!control.is_paused() && control.paused_time() >= pause);
+}
@@ -0,0 +1,159 @@
+// Endpoints of the localhost preview server, spoken to over plain TCP
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::time::Duration;
+use std::time::Instant;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::renderer::Progress;
+use lib::gfx::renderer::RenderControl;
+use lib::gfx::renderer::RenderControlTraits;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::server::MAX_CLIENTS;
+use lib::gfx::server::PreviewServer;
+use lib::gfx::server::PreviewServerTraits;
+
+use lib::gfx::stats::RenderStats;
+
+// Status line and body of one request, sent the way the server's own page sends it
+fn request(address: SocketAddr, method: &str, path: &str) -> (String, Vec<u8>) {
+    let headers = format!("Host: {}\r\nOrigin: http://{}\r\n", address, address);
+    request_with(address, method, path, &headers)
+}
+
+fn request_with(address: SocketAddr, method: &str, path: &str, headers: &str) -> (String, Vec<u8>) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\n{}\r\n", method, path, headers).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n").expect("no end of headers");
+    let head = String::from_utf8_lossy(&response[..split]).into_owned();
+    (head.lines().next().unwrap().to_string(), response[split + 4..].to_vec())
+}
+
+fn text(body: Vec<u8>) -> String {
+    String::from_utf8(body).unwrap()
+}
+
+#[test]
+fn serves_page_image_and_progress() {
+    let server = PreviewServer::start(0, RenderControl::new()).unwrap();
+    let address = server.address();
+    assert!(address.ip().is_loopback());
+
+    let (status, page) = request(address, "GET", "/");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(text(page).contains("/image.png"));
+    // nothing rendered yet
+    assert_eq!(request(address, "GET", "/image.png").0, "HTTP/1.1 503 Service Unavailable");
+
+    let mut screen = Screen::empty_screen(4, 2);
+    screen.color_pixel(0, 0, Color::new((1.0, 0.5, 0.0)), 1);
+    server.publish_image(&screen);
+    let stats = RenderStats { primary_rays: 8, secondary_rays: 4, ..RenderStats::default() };
+    server.publish_progress(&Progress { pixels_done: 8, pixels: 32, passes_done: 0, passes: 2, stats, elapsed: Duration::from_secs(1) });
+
+    let (status, png) = request(address, "GET", "/image.png?v=1");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    let progress = text(request(address, "GET", "/progress").1);
+    for field in ["\"status\": \"rendering\"", "\"image_version\": 1", "\"percent\": 25.00", "\"passes\": 2",
+        "\"total_rays\": 12", "\"average_depth\": 1.500"].iter() {
+        assert!(progress.contains(field), "{} not in {}", field, progress);
+    }
+
+    server.finish();
+    assert!(text(request(address, "GET", "/progress").1).contains("\"status\": \"finished\""));
+}
+
+#[test]
+fn pauses_resumes_and_cancels() {
+    let control = RenderControl::new();
+    let server = PreviewServer::start(0, control.clone()).unwrap();
+    let address = server.address();
+
+    let (status, body) = request(address, "POST", "/pause");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(control.is_paused());
+    assert!(text(body).contains("\"status\": \"paused\""));
+
+    request(address, "POST", "/resume");
+    assert!(!control.is_paused());
+
+    request(address, "POST", "/cancel");
+    assert!(control.is_cancelled());
+    assert!(text(request(address, "GET", "/progress").1).contains("\"status\": \"cancelled\""));
+}
+
+#[test]
+fn rejects_unknown_requests() {
+    let control = RenderControl::new();
+    let server = PreviewServer::start(0, control.clone()).unwrap();
+    assert_eq!(request(server.address(), "GET", "/cancel").0, "HTTP/1.1 405 Method Not Allowed");
+    assert_eq!(request(server.address(), "GET", "/elsewhere").0, "HTTP/1.1 404 Not Found");
+    assert!(!control.is_cancelled());
+}
+
+#[test]
+fn answers_only_to_its_own_host_name() {
+    let server = PreviewServer::start(0, RenderControl::new()).unwrap();
+    let address = server.address();
+    let localhost = format!("Host: localhost:{}\r\n", address.port());
+    assert_eq!(request_with(address, "GET", "/progress", &localhost).0, "HTTP/1.1 200 OK");
+    for headers in ["", "Host: localhost\r\n", "Host: evil.example:80\r\n", &format!("Host: evil.example:{}\r\n", address.port())] {
+        assert_eq!(request_with(address, "GET", "/progress", headers).0, "HTTP/1.1 403 Forbidden", "{:?}", headers);
+    }
+}
+
+#[test]
+fn takes_commands_only_from_its_own_page() {
+    let control = RenderControl::new();
+    let server = PreviewServer::start(0, control.clone()).unwrap();
+    let address = server.address();
+    let host = format!("Host: {}\r\n", address);
+    for origin in ["", "Origin: null\r\n", "Origin: http://evil.example\r\n", &format!("Origin: https://{}\r\n", address)] {
+        let headers = format!("{}{}", host, origin);
+        assert_eq!(request_with(address, "POST", "/cancel", &headers).0, "HTTP/1.1 403 Forbidden", "{:?}", origin);
+    }
+    assert!(!control.is_cancelled());
+
+    let headers = format!("{}Origin: http://localhost:{}\r\n", host, address.port());
+    assert_eq!(request_with(address, "POST", "/cancel", &headers).0, "HTTP/1.1 200 OK");
+    assert!(control.is_cancelled());
+}
+
+#[test]
+fn an_idle_client_holds_up_nobody() {
+    let server = PreviewServer::start(0, RenderControl::new()).unwrap();
+    let address = server.address();
+    let _idle = TcpStream::connect(address).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(request(address, "GET", "/progress").0, "HTTP/1.1 200 OK");
+    drop(server);
+    assert!(start.elapsed() < Duration::from_secs(2), "took {:?}", start.elapsed());
+}
+
+#[test]
+fn turns_away_clients_past_the_limit() {
+    let server = PreviewServer::start(0, RenderControl::new()).unwrap();
+    let address = server.address();
+    let mut idle: Vec<TcpStream> = (0..MAX_CLIENTS).map(|_| TcpStream::connect(address).unwrap()).collect();
+    assert_eq!(request(address, "GET", "/progress").0, "HTTP/1.1 503 Service Unavailable");
+
+    // a client leaving makes room for the next
+    idle.pop();
+    let start = Instant::now();
+    while request(address, "GET", "/progress").0 != "HTTP/1.1 200 OK" {
+        assert!(start.elapsed() < Duration::from_secs(2), "still turned away");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
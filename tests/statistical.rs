@@ -0,0 +1,533 @@
+// Statistical checks of the sampling routines and materials:
+//  - chi-square goodness of fit between what a sampler draws and the density it claims
+//  - white furnace, no material may reflect more energy than it receives
+//  - reciprocity of every BSDF that is evaluated for light sampling
+// To cover a new material add it to material_cases() with the properties it promises.
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use lib::basic_types::random;
+use lib::basic_types::spectrum;
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Point3;
+use lib::basic_types::vec3::Vec3;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::basic_types::ray::Ray;
+use lib::basic_types::ray::RayTraits;
+
+use lib::gfx::hittable::HitRecord;
+use lib::gfx::hittable::HitRecordTraits;
+use lib::gfx::hittable::hittables::Hittable;
+use lib::gfx::hittable::hittables::sphere::Sphere;
+use lib::gfx::hittable::hittables::sphere::SphereData;
+
+use lib::gfx::material::Material;
+use lib::gfx::material::coated::CoatedMaterial;
+use lib::gfx::material::coated::CoatedMaterialFn;
+use lib::gfx::material::conductor::Conductor;
+use lib::gfx::material::conductor::ConductorFn;
+use lib::gfx::material::dielectric::Dielectric;
+use lib::gfx::material::dielectric::DielectricFn;
+use lib::gfx::material::lambertian::Lambertian;
+use lib::gfx::material::lambertian::LambertianFn;
+use lib::gfx::material::metal::Metal;
+use lib::gfx::material::metal::MetalFn;
+use lib::gfx::material::microfacet::Ggx;
+use lib::gfx::material::microfacet::GgxTraits;
+use lib::gfx::material::mix::MixMaterial;
+use lib::gfx::material::mix::MixMaterialFn;
+use lib::gfx::material::normal_mapped::NormalMapped;
+use lib::gfx::material::normal_mapped::NormalMappedFn;
+use lib::gfx::material::oren_nayar::OrenNayar;
+use lib::gfx::material::oren_nayar::OrenNayarFn;
+use lib::gfx::material::principled;
+use lib::gfx::material::principled::Principled;
+use lib::gfx::material::principled::PrincipledFn;
+use lib::gfx::material::rough_dielectric::RoughDielectric;
+use lib::gfx::material::rough_dielectric::RoughDielectricFn;
+use lib::gfx::material::sheen::Sheen;
+use lib::gfx::material::sheen::SheenFn;
+use lib::gfx::material::subsurface::Subsurface;
+use lib::gfx::material::subsurface::SubsurfaceFn;
+use lib::gfx::material::thin_dielectric::ThinDielectric;
+use lib::gfx::material::thin_dielectric::ThinDielectricFn;
+use lib::gfx::material::thin_film::ThinFilm;
+use lib::gfx::material::thin_film::ThinFilmFn;
+
+use lib::gfx::medium::Medium;
+use lib::gfx::medium::henyey_greenstein::HenyeyGreenstein;
+use lib::gfx::medium::henyey_greenstein::HenyeyGreensteinTraits;
+use lib::gfx::medium::homogeneous::Homogeneous;
+use lib::gfx::medium::homogeneous::HomogeneousFn;
+
+use lib::gfx::texture::Texture;
+use lib::gfx::texture::checker::CheckerTexture;
+use lib::gfx::texture::checker::CheckerTextureFn;
+use lib::gfx::texture::solid_color::SolidColor;
+use lib::gfx::texture::solid_color::SolidColorFn;
+
+const SEED: u64 = 42;
+
+// Directions are binned by cos(theta) and phi about +z, which gives equal area cells
+const THETA_BINS: usize = 10;
+const PHI_BINS: usize = 20;
+// Midpoint rule resolution used to integrate a density over one cell, finer in
+// theta where peaked lobes like GGX and Henyey-Greenstein vary fastest
+const COS_SUBDIVISIONS: usize = 256;
+const PHI_SUBDIVISIONS: usize = 8;
+// Cells expecting fewer samples than this are pooled, as the test requires
+const MIN_EXPECTED: f64 = 5.0;
+// Tests are seeded, so this is the chance a correct sampler was flagged when the seed was chosen
+const SIGNIFICANCE: f64 = 1e-3;
+
+const SAMPLES: usize = 100_000;
+const FURNACE_SAMPLES: usize = 20_000;
+
+// ln Gamma(x) from the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [76.18009172947146, -86.50532032941677, 24.01409824083091,
+        -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+    let mut y = x;
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let mut series = 1.000000000190015;
+    for c in COEFFICIENTS.iter() {
+        y += 1.0;
+        series += c / y;
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+// Regularized upper incomplete gamma function Q(a, x)
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut n = a;
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        1.0 - sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+    } else {
+        // Lentz's continued fraction
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (-x + a * x.ln() - ln_gamma(a)).exp() * h
+    }
+}
+
+// Pearson's test of observed against expected counts, the last cell of both
+// holds the samples that were rejected or fell outside the binned domain
+fn chi_square_test(name: &str, observed: &[f64], expected: &[f64]) {
+    let mut statistic = 0.0;
+    let mut cells = 0;
+    let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+    for (o, e) in observed.iter().zip(expected) {
+        if *e < MIN_EXPECTED {
+            pooled_observed += o;
+            pooled_expected += e;
+        } else {
+            statistic += (o - e) * (o - e) / e;
+            cells += 1;
+        }
+    }
+    if pooled_observed > 0.0 || pooled_expected > 0.0 {
+        assert!(pooled_expected > 0.0 || pooled_observed < MIN_EXPECTED,
+            "{}: {} samples landed where the density is zero", name, pooled_observed);
+        if pooled_expected > 0.0 {
+            let e = pooled_expected.max(MIN_EXPECTED);
+            statistic += (pooled_observed - pooled_expected) * (pooled_observed - pooled_expected) / e;
+            cells += 1;
+        }
+    }
+    let dof = (cells - 1).max(1) as f64;
+    let p = gamma_q(dof / 2.0, statistic / 2.0);
+    assert!(p > SIGNIFICANCE, "{}: chi-square {:.1} with {} degrees of freedom, p = {:.2e}",
+        name, statistic, dof, p);
+}
+
+fn direction_cell(d: Vec3) -> usize {
+    let cos_theta = d.z().clamp(-1.0, 1.0);
+    let t = (((cos_theta + 1.0) / 2.0 * THETA_BINS as f64) as usize).min(THETA_BINS - 1);
+    let phi = d.y().atan2(d.x()) + PI;
+    let p = ((phi / (2.0 * PI) * PHI_BINS as f64) as usize).min(PHI_BINS - 1);
+    t * PHI_BINS + p
+}
+
+fn direction_from(cos_theta: f64, phi: f64) -> Vec3 {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = phi - PI;
+    Vec3::new((sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+}
+
+// Draws samples of unit directions, None for a rejected sample, and tests them against pdf
+fn check_directions<S, P>(name: &str, samples: usize, mut sample: S, pdf: P)
+    where S: FnMut() -> Option<Vec3>, P: Fn(Vec3) -> f64 {
+    let cells = THETA_BINS * PHI_BINS;
+    let mut observed = vec![0.0; cells + 1];
+    for _ in 0..samples {
+        match sample() {
+            Some(d) => observed[direction_cell(d.unitize())] += 1.0,
+            None => observed[cells] += 1.0
+        }
+    }
+
+    let mut expected = vec![0.0; cells + 1];
+    let d_cos = 2.0 / THETA_BINS as f64;
+    let d_phi = 2.0 * PI / PHI_BINS as f64;
+    let (sub_cos, sub_phi) = (COS_SUBDIVISIONS as f64, PHI_SUBDIVISIONS as f64);
+    for t in 0..THETA_BINS {
+        for p in 0..PHI_BINS {
+            let mut integral = 0.0;
+            for i in 0..COS_SUBDIVISIONS {
+                for j in 0..PHI_SUBDIVISIONS {
+                    let cos_theta = -1.0 + (t as f64 + (i as f64 + 0.5) / sub_cos) * d_cos;
+                    let phi = (p as f64 + (j as f64 + 0.5) / sub_phi) * d_phi;
+                    integral += pdf(direction_from(cos_theta, phi));
+                }
+            }
+            expected[t * PHI_BINS + p] = integral * d_cos * d_phi / (sub_cos * sub_phi) * samples as f64;
+        }
+    }
+    let total: f64 = expected[..cells].iter().sum();
+    expected[cells] = (samples as f64 - total).max(0.0);
+    chi_square_test(name, &observed, &expected);
+}
+
+// One dimensional version over [min, max) with the density's integral over each bin
+fn check_scalar<S, C>(name: &str, samples: usize, bins: usize, min: f64, max: f64, mut sample: S, bin_mass: C)
+    where S: FnMut() -> f64, C: Fn(f64, f64) -> f64 {
+    let width = (max - min) / bins as f64;
+    let mut observed = vec![0.0; bins + 1];
+    for _ in 0..samples {
+        let x = sample();
+        if x >= min && x < max {
+            observed[(((x - min) / width) as usize).min(bins - 1)] += 1.0;
+        } else {
+            observed[bins] += 1.0;
+        }
+    }
+    let mut expected: Vec<f64> = (0..bins)
+        .map(|i| bin_mass(min + i as f64 * width, min + (i + 1) as f64 * width) * samples as f64)
+        .collect();
+    let total: f64 = expected.iter().sum();
+    expected.push((samples as f64 - total).max(0.0));
+    chi_square_test(name, &observed, &expected);
+}
+
+fn uniform() -> f64 {
+    random::rng().gen_range(0.0, 1.0)
+}
+
+#[test]
+fn random_unit_vector_is_unit_length() {
+    random::seed(SEED);
+    for _ in 0..10_000 {
+        let v = Vec3::random_unit_vector();
+        assert!((v.length() - 1.0).abs() < 1e-9, "length {}", v.length());
+    }
+}
+
+#[test]
+fn random_unit_vector_is_uniform() {
+    random::seed(SEED);
+    check_directions("random_unit_vector", SAMPLES, || Some(Vec3::random_unit_vector()), |_| 1.0 / (4.0 * PI));
+}
+
+#[test]
+fn random_in_unit_sphere_is_uniform() {
+    random::seed(SEED);
+    check_directions("random_in_unit_sphere direction", SAMPLES,
+        || Some(Vec3::random_in_unit_sphere()), |_| 1.0 / (4.0 * PI));
+    // the enclosed volume, r^3, is uniform for points spread evenly through the ball
+    check_scalar("random_in_unit_sphere radius", SAMPLES, 20, 0.0, 1.0,
+        || Vec3::random_in_unit_sphere().length().powi(3), |a, b| b - a);
+}
+
+#[test]
+fn random_cosine_direction_matches_pdf() {
+    random::seed(SEED);
+    check_directions("random_cosine_direction", SAMPLES,
+        || Some(Vec3::random_cosine_direction()), |d| d.z().max(0.0) / PI);
+}
+
+#[test]
+fn ggx_visible_normals_match_pdf() {
+    random::seed(SEED);
+    for (alpha_x, alpha_y) in [(0.5, 0.5), (0.3, 0.7), (0.9, 0.9)].iter() {
+        let ggx = Ggx::new(*alpha_x, *alpha_y);
+        for cos_o in [1.0, 0.6, 0.2].iter() {
+            let wo = direction_from(*cos_o, 0.7);
+            let name = format!("ggx alpha ({}, {}) cos_o {}", alpha_x, alpha_y, cos_o);
+            check_directions(&name, SAMPLES,
+                || Some(ggx.sample_visible_normal(wo, uniform(), uniform())), |wm| ggx.pdf(wo, wm));
+        }
+    }
+}
+
+// Midpoint rule integral of f over [a, b)
+fn integrate<F: Fn(f64) -> f64>(f: F, a: f64, b: f64) -> f64 {
+    let steps = 32;
+    let h = (b - a) / steps as f64;
+    (0..steps).map(|i| f(a + (i as f64 + 0.5) * h) * h).sum()
+}
+
+// The clearcoat's GTR1 is far narrower than direction cells at high gloss, so
+// its half vectors are binned by ln(tan(theta)), fine near the peak and coarse
+// in the long tail. Per unit of that the density is 2 pi D cos^2 sin^2.
+#[test]
+fn gtr1_samples_match_distribution() {
+    random::seed(SEED);
+    for alpha in [0.001, 0.01, 0.1].iter() {
+        let density = |x: f64| {
+            let theta = x.exp().atan();
+            let (sin, cos) = theta.sin_cos();
+            2.0 * PI * principled::gtr1(cos, *alpha) * cos * cos * sin * sin
+        };
+        check_scalar(&format!("sample_gtr1 alpha {}", alpha), SAMPLES, 60, -12.0, 7.0,
+            || {
+                let h = principled::sample_gtr1(*alpha, uniform(), uniform());
+                (h.x().hypot(h.y()) / h.z()).ln()
+            },
+            |a, b| integrate(density, a, b));
+    }
+}
+
+#[test]
+fn henyey_greenstein_matches_phase_function() {
+    random::seed(SEED);
+    let forward = Vec3::new((0.0, 0.0, 1.0));
+    for g in [-0.5, 0.0, 0.3, 0.8].iter() {
+        let phase = HenyeyGreenstein::new(*g);
+        check_directions(&format!("henyey greenstein g {}", g), SAMPLES,
+            || Some(phase.sample(forward, uniform(), uniform())), |d| phase.p(d.z()));
+    }
+}
+
+#[test]
+fn wavelength_sampling_matches_pdf() {
+    random::seed(SEED);
+    check_scalar("sample_wavelength", SAMPLES, 47, spectrum::LAMBDA_MIN, spectrum::LAMBDA_MAX,
+        || spectrum::sample_wavelength(uniform()),
+        |a, b| integrate(spectrum::wavelength_pdf, a, b));
+}
+
+// Distance to where the medium scattered r, infinite when it reached t_max
+fn free_flight(medium: &Homogeneous, r: &Ray, t_max: f64, throughput: Color) -> f64 {
+    match medium.sample(r, t_max, throughput).1 {
+        Some(scattered) => (scattered.origin() - r.origin()).length(),
+        None => f64::INFINITY
+    }
+}
+
+#[test]
+fn homogeneous_medium_samples_free_flight_distances() {
+    random::seed(SEED);
+    let sigma_t = Color::new((0.5, 1.0, 4.0));
+    let medium = Homogeneous::new(0.25 * sigma_t, 0.75 * sigma_t, 0.0);
+    // a direction of length two, so the boundary at t_max = 1.5 is 3 units away
+    let r = Ray::new(Point3::new((0.0, 0.0, 0.0)), Vec3::new((0.0, 0.0, 2.0)));
+    let distance = 3.0;
+
+    // RGB paths draw from the channels' exponentials mixed by path throughput
+    for throughput in [white(), Color::new((0.2, 0.5, 1.0))].iter() {
+        let p = *throughput / (throughput.x() + throughput.y() + throughput.z());
+        let mass = |s: f64, a: f64, b: f64| (-s * a).exp() - (-s * b).exp();
+        check_scalar(&format!("homogeneous throughput {:?}", throughput), SAMPLES, 30, 0.0, distance,
+            || free_flight(&medium, &r, distance / 2.0, *throughput),
+            |a, b| p.x() * mass(sigma_t.x(), a, b) + p.y() * mass(sigma_t.y(), a, b) + p.z() * mass(sigma_t.z(), a, b));
+    }
+
+    // a spectral path sees the one extinction at its wavelength
+    for lambda in [450.0, 550.0, 650.0].iter() {
+        let s = spectrum::rgb_to_spectrum(sigma_t, *lambda);
+        let r = r.with_wavelength(Some(*lambda));
+        check_scalar(&format!("homogeneous wavelength {}", lambda), SAMPLES, 30, 0.0, distance,
+            || free_flight(&medium, &r, distance / 2.0, white()),
+            |a, b| (-s * a).exp() - (-s * b).exp());
+    }
+}
+
+struct MaterialCase {
+    name: &'static str,
+    material: Rc<dyn Material>,
+    // scatter draws directions with exactly the density reported by pdf
+    sampled_by_pdf: bool,
+    // eval(wo, wi) / cos_i equals eval(wi, wo) / cos_o
+    reciprocal: bool,
+    // with these white parameters no energy is lost, so the furnace returns one
+    lossless: bool
+}
+
+fn constant(value: f64) -> Rc<dyn Texture> {
+    Rc::new(SolidColor::from_scalar(value))
+}
+
+fn white() -> Color {
+    Color::new((1.0, 1.0, 1.0))
+}
+
+fn case(name: &'static str, material: Rc<dyn Material>, sampled_by_pdf: bool, reciprocal: bool, lossless: bool) -> MaterialCase {
+    MaterialCase { name, material, sampled_by_pdf, reciprocal, lossless }
+}
+
+// Every material with parameters that make the checks meaningful
+fn material_cases() -> Vec<MaterialCase> {
+    let lambertian: Rc<dyn Material> = Rc::new(Lambertian::new(white()));
+    let gold: Rc<dyn Material> = Rc::new(Conductor::gold(0.5));
+    let checker: Rc<dyn Texture> = Rc::new(CheckerTexture::new(constant(0.0), constant(1.0), 10.0));
+    let mut layered = Principled::new(Rc::new(SolidColor::new(white())));
+    layered.set_metallic(constant(0.5));
+    layered.set_sheen(constant(1.0));
+    layered.set_clearcoat(constant(1.0));
+    // the glossiest clearcoat is narrower than the direction cells can resolve,
+    // gtr1_samples_match_distribution covers it
+    layered.set_clearcoat_gloss(constant(0.0));
+    vec![
+        case("lambertian", lambertian.clone(), true, true, true),
+        case("metal", Rc::new(Metal::new(white(), 0.3)), false, false, false),
+        case("dielectric", Rc::new(Dielectric::new(1.5)), false, false, true),
+        case("conductor", gold.clone(), true, true, false),
+        case("tinted conductor", Rc::new(Conductor::from_reflectance(Color::new((0.9, 0.6, 0.3)), 0.4)), true, true, false),
+        case("anisotropic conductor", Rc::new(Conductor::new_anisotropic(Color::new((0.2, 0.9, 1.1)),
+            Color::new((3.9, 2.5, 2.1)), 0.3, 0.7)), true, true, false),
+        case("rough dielectric", Rc::new(RoughDielectric::new(1.5, 0.4)), false, false, false),
+        case("thin dielectric", Rc::new(ThinDielectric::new(1.5)), false, false, true),
+        case("principled", Rc::new(Principled::new(Rc::new(SolidColor::new(white())))), true, true, false),
+        case("principled layered", Rc::new(layered), true, true, false),
+        case("mix", Rc::new(MixMaterial::new(lambertian.clone(), gold.clone(), 0.5)), true, true, false),
+        case("coated", Rc::new(CoatedMaterial::new(lambertian.clone(), 1.5)), false, false, false),
+        case("oren nayar", Rc::new(OrenNayar::new(white(), 30.0)), true, true, false),
+        case("sheen", Rc::new(Sheen::new(white(), white(), 0.5)), true, true, false),
+        case("normal mapped", Rc::new(NormalMapped::new_bump_map(lambertian, checker, 0.01)), false, false, false),
+        case("thin film conductor", Rc::new(ThinFilm::new_conductor(Color::new((2.7, 2.5, 2.2)),
+            Color::new((3.8, 3.4, 3.0)), 0.5, 2.4, constant(120.0))), true, true, false),
+        case("soap bubble", Rc::new(ThinFilm::soap_bubble(constant(300.0))), false, false, true),
+        case("subsurface", Rc::new(Subsurface::new(white(), white(), 0.0)), false, false, true)
+    ]
+}
+
+// Hit on the +z pole of a unit sphere seen from direction wo, so the local
+// shading frame is the world frame
+fn hit_from(material: Rc<dyn Material>, wo: Vec3) -> (Ray, HitRecord) {
+    let sphere = SphereData::new(Point3::new((0.0, 0.0, 0.0)), 1.0, material);
+    let pole = Point3::new((0.0, 0.0, 1.0));
+    let r = Ray::new(pole + 2.0 * wo, -wo);
+    let rec = sphere.hit(r, 0.001, f64::INFINITY).expect("ray aimed at the pole must hit");
+    (r, rec)
+}
+
+#[test]
+fn tinted_conductor_reflects_its_color_head_on() {
+    let color = Color::new((0.9, 0.6, 0.3));
+    let material: Rc<dyn Material> = Rc::new(Conductor::from_reflectance(color, 0.0));
+    let (r, rec) = hit_from(material.clone(), Vec3::new((0.0, 0.0, 1.0)));
+    let (attenuation, _, flag) = material.scatter(&r, &rec);
+    assert!(flag);
+    assert!((attenuation - color).length() < 1e-3, "reflects {:?} instead of {:?}", attenuation, color);
+}
+
+// Incident directions the materials are checked from, down to fairly grazing
+const COS_INCIDENT: [f64; 3] = [0.95, 0.6, 0.2];
+
+#[test]
+fn materials_sample_their_pdf() {
+    for case in material_cases().into_iter().filter(|c| c.sampled_by_pdf) {
+        random::seed(SEED);
+        for cos_o in COS_INCIDENT.iter() {
+            let wo = direction_from(*cos_o, 0.4);
+            let (r, rec) = hit_from(case.material.clone(), wo);
+            let name = format!("{} cos_o {}", case.name, cos_o);
+            check_directions(&name, SAMPLES,
+                || {
+                    let (_, scattered, flag) = case.material.scatter(&r, &rec);
+                    if flag {Some(scattered.direction())} else {None}
+                },
+                |wi| case.material.pdf(&r, &rec, wi));
+        }
+    }
+}
+
+#[test]
+fn materials_pass_white_furnace() {
+    for case in material_cases() {
+        random::seed(SEED);
+        for cos_o in COS_INCIDENT.iter() {
+            let wo = direction_from(*cos_o, 0.4);
+            let (r, rec) = hit_from(case.material.clone(), wo);
+            let mut sum = Color::new((0.0, 0.0, 0.0));
+            let mut sum_squared = Color::new((0.0, 0.0, 0.0));
+            for _ in 0..FURNACE_SAMPLES {
+                let (attenuation, _, flag) = case.material.scatter(&r, &rec);
+                if flag {
+                    sum += attenuation;
+                    sum_squared += attenuation * attenuation;
+                }
+            }
+            let n = FURNACE_SAMPLES as f64;
+            let mean = sum / n;
+            let variance = sum_squared / n - mean * mean;
+            let channels = [(mean.x(), variance.x()), (mean.y(), variance.y()), (mean.z(), variance.z())];
+            for (m, v) in channels.iter() {
+                // four standard errors plus a little slack for the estimator itself
+                let tolerance = 4.0 * (v.max(0.0) / n).sqrt() + 0.005;
+                assert!(*m <= 1.0 + tolerance, "{} cos_o {}: reflects {:.4} of the incident energy",
+                    case.name, cos_o, m);
+                if case.lossless {
+                    assert!(*m >= 1.0 - tolerance, "{} cos_o {}: loses energy, reflects only {:.4}",
+                        case.name, cos_o, m);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn materials_are_reciprocal() {
+    for case in material_cases().into_iter().filter(|c| c.reciprocal) {
+        random::seed(SEED);
+        let (_, rec) = hit_from(case.material.clone(), Vec3::new((0.0, 0.0, 1.0)));
+        let pole = rec.p();
+        for _ in 0..1000 {
+            let wo = direction_from(uniform().max(0.05), 2.0 * PI * uniform());
+            let wi = direction_from(uniform().max(0.05), 2.0 * PI * uniform());
+            let towards = case.material.eval(&Ray::new(pole + wo, -wo), &rec, wi) / wi.z();
+            let back = case.material.eval(&Ray::new(pole + wi, -wi), &rec, wo) / wo.z();
+            let difference = (towards - back).length();
+            let scale = towards.length().max(back.length()).max(1.0);
+            assert!(difference <= 1e-6 * scale, "{}: f(wo, wi) = {:?} but f(wi, wo) = {:?} for wo {:?} wi {:?}",
+                case.name, towards, back, wo, wi);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Layout and colors of the half block terminal preview
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::terminal::ColorSupport;
+use lib::gfx::terminal::TerminalPreview;
+use lib::gfx::terminal::TerminalPreviewTraits;
+
+// Red top half over a blue bottom half, stored gamma encoded like a render
+fn split_screen(width: i32, height: i32) -> Screen {
+    let mut screen = Screen::empty_screen(width, height);
+    for j in 0..height {
+        for i in 0..width {
+            let color = if j >= height / 2 {Color::new((1.0, 0.0, 0.0))} else {Color::new((0.0, 0.0, 1.0))};
+            screen.color_pixel(i as usize, j as usize, color, 1);
+        }
+    }
+    screen
+}
+
+#[test]
+fn fits_the_terminal_keeping_the_aspect_ratio() {
+    let wide = TerminalPreview::new(ColorSupport::TrueColor, 80, 24, 384, 216, true);
+    assert_eq!((wide.columns(), wide.lines()), (78, 22));
+    let tall = TerminalPreview::new(ColorSupport::TrueColor, 80, 12, 384, 216, true);
+    assert_eq!((tall.columns(), tall.lines()), (35, 10));
+    // never wider than the image itself
+    let small = TerminalPreview::new(ColorSupport::TrueColor, 80, 24, 16, 8, true);
+    assert_eq!((small.columns(), small.lines()), (16, 4));
+}
+
+#[test]
+fn draws_two_pixels_per_cell() {
+    let preview = TerminalPreview::new(ColorSupport::TrueColor, 80, 24, 16, 8, true);
+    let frame = preview.frame(&split_screen(16, 8));
+    let lines: Vec<&str> = frame.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines.iter().all(|line| line.matches('\u{2580}').count() == 16 && line.ends_with("\x1b[0m")));
+    assert!(lines[0].starts_with("\x1b[38;2;255;0;0m\x1b[48;2;255;0;0m"));
+    assert!(lines[3].starts_with("\x1b[38;2;0;0;255m\x1b[48;2;0;0;255m"));
+}
+
+#[test]
+fn falls_back_to_the_256_color_cube() {
+    let preview = TerminalPreview::new(ColorSupport::Ansi256, 80, 24, 16, 8, true);
+    let frame = preview.frame(&split_screen(16, 8));
+    // pure red and blue are cube entries 16 + 36 * 5 and 16 + 5
+    assert!(frame.starts_with("\x1b[38;5;196m\x1b[48;5;196m"));
+    assert!(frame.contains("\x1b[38;5;21m\x1b[48;5;21m"));
+}
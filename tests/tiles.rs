@@ -0,0 +1,176 @@
+// Tile orders, crop regions and merging a cropped render into a full image
+use std::collections::HashSet;
+use std::fs;
+
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::compare::Image;
+use lib::gfx::compare::ImageTraits;
+
+use lib::gfx::renderer::Renderer;
+use lib::gfx::renderer::RendererTraits;
+use lib::gfx::renderer::RenderSettings;
+use lib::gfx::renderer::RenderSettingsTraits;
+
+use lib::gfx::scene;
+
+use lib::gfx::screen::Screen;
+use lib::gfx::screen::ScreenTraits;
+
+use lib::gfx::tile;
+use lib::gfx::tile::Tile;
+use lib::gfx::tile::TileTraits;
+use lib::gfx::tile::TileOrder;
+use lib::gfx::tile::TileOrderTraits;
+
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+const ORDERS: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+fn settings() -> RenderSettings {
+    RenderSettings { width: 32, height: 18, samples_per_pixel: 4, max_depth: 8, seed: 5, ..RenderSettings::default() }
+}
+
+fn pixels(screen: &Screen) -> Vec<(f64, f64, f64)> {
+    let mut pixels = Vec::new();
+    for j in 0..screen.height() as usize {
+        for i in 0..screen.width() as usize {
+            let c = screen.pixel(i, j);
+            pixels.push((c.x(), c.y(), c.z()));
+        }
+    }
+    pixels
+}
+
+// Column and row from the top of the tile in a grid of size x size tiles laid over region
+fn cell(region: Tile, size: i32, tile: &Tile) -> (i32, i32) {
+    ((tile.x0 - region.x0) / size, (region.y1 - tile.y1) / size)
+}
+
+#[test]
+fn every_order_covers_the_region_once() {
+    let region = Tile::new(3, 2, 40, 27);
+    for order in ORDERS {
+        let tiles = tile::schedule(region, 8, 8, order);
+        // 5 columns, the last 5 wide, and 4 rows, the last 1 high
+        assert_eq!(tiles.len(), 20, "{:?}", order);
+        let mut seen = HashSet::new();
+        for tile in &tiles {
+            assert!(tile.width() <= 8 && tile.height() <= 8);
+            for (i, j) in tile.coordinates() {
+                assert!(region.contains(i, j), "{:?} left the region at ({}, {})", order, i, j);
+                assert!(seen.insert((i, j)), "{:?} covered ({}, {}) twice", order, i, j);
+            }
+        }
+        assert_eq!(seen.len(), region.pixels());
+    }
+}
+
+#[test]
+fn scanline_goes_from_the_top_left() {
+    let tiles = tile::schedule(Tile::image(20, 10), 8, 8, TileOrder::Scanline);
+    let corners: Vec<(i32, i32)> = tiles.iter().map(|t| (t.x0, t.y1)).collect();
+    assert_eq!(corners, vec![(0, 10), (8, 10), (16, 10), (0, 2), (8, 2), (16, 2)]);
+}
+
+#[test]
+fn spiral_starts_at_the_center() {
+    let region = Tile::image(56, 40);
+    let tiles = tile::schedule(region, 8, 8, TileOrder::Spiral);
+    let cells: Vec<(i32, i32)> = tiles.iter().map(|t| cell(region, 8, t)).collect();
+    // 7 x 5 grid, center first, then the ring around it
+    assert_eq!(cells[0], (3, 2));
+    let first_ring: HashSet<(i32, i32)> = cells[1..9].iter().copied().collect();
+    assert!(first_ring.iter().all(|&(c, r)| (c - 3).abs() <= 1 && (r - 2).abs() <= 1));
+    assert_eq!(first_ring.len(), 8);
+}
+
+#[test]
+fn hilbert_steps_between_neighbours() {
+    let region = Tile::image(64, 64);
+    let tiles = tile::schedule(region, 8, 8, TileOrder::Hilbert);
+    let cells: Vec<(i32, i32)> = tiles.iter().map(|t| cell(region, 8, t)).collect();
+    assert_eq!(cells.len(), 64);
+    assert!(cells.windows(2).all(|pair| (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs() == 1));
+}
+
+#[test]
+fn order_and_size_leave_the_image_unchanged() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let rows = Renderer::new(&scene, settings()).render();
+    for order in ORDERS {
+        let settings = RenderSettings { tile_size: Some(5), tile_order: order, ..settings() };
+        let tiled = Renderer::new(&scene, settings).render();
+        assert_eq!(pixels(&rows.screen), pixels(&tiled.screen), "{:?}", order);
+    }
+}
+
+#[test]
+fn renders_only_the_region() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let full = Renderer::new(&scene, settings()).render();
+    let region = Tile::from_top_down(4, 3, 20, 12, 18);
+    let settings = RenderSettings { region: Some(region), tile_size: Some(4), ..settings() };
+    let cropped = Renderer::new(&scene, settings).render();
+
+    assert_eq!(cropped.stats.primary_rays, 16 * 9 * 4);
+    for j in 0..18 {
+        for i in 0..32 {
+            let (a, b) = (full.screen.pixel(i, j), cropped.screen.pixel(i, j));
+            if region.contains(i as i32, j as i32) {
+                assert_eq!((a.x(), a.y(), a.z()), (b.x(), b.y(), b.z()), "pixel ({}, {})", i, j);
+            } else {
+                assert_eq!(b.length(), 0.0, "pixel ({}, {}) outside the region", i, j);
+            }
+        }
+    }
+}
+
+#[test]
+fn region_is_clipped_to_the_image() {
+    let settings = RenderSettings { region: Some(Tile::new(-5, 10, 100, 40)), ..settings() };
+    assert_eq!(settings.region(), Tile::new(0, 10, 32, 18));
+    assert_eq!(settings.tiles().len(), 8);
+}
+
+#[test]
+fn merges_the_region_into_an_earlier_image() {
+    let scene = scene::two_spheres(ASPECT_RATIO);
+    let earlier = Renderer::new(&scene, RenderSettings { seed: 99, ..settings() }).render().screen;
+    let region = Tile::new(8, 4, 24, 10);
+    let cropped = Renderer::new(&scene, RenderSettings { region: Some(region), ..settings() }).render().screen;
+
+    // through a PPM file and back, as main does with --merge
+    let path = std::env::temp_dir().join(format!("rust_tracer_tiles_{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    earlier.write_to_ppm(path.to_string());
+    let file = format!("{}.ppm", path);
+    let image = Image::load(&file).unwrap();
+    fs::remove_file(&file).unwrap();
+
+    let reloaded = image.to_screen();
+    let mut merged = image.to_screen();
+    merged.paste(&cropped, region);
+    for j in 0..18 {
+        for i in 0..32 {
+            if region.contains(i as i32, j as i32) {
+                assert_eq!(merged.pixel(i, j), cropped.pixel(i, j), "pixel ({}, {})", i, j);
+            } else {
+                assert_eq!(merged.pixel(i, j), reloaded.pixel(i, j), "pixel ({}, {})", i, j);
+                // the file keeps 8 bits of the clamped color
+                let c = earlier.pixel(i, j);
+                let clamped = Color::new((c.x().min(1.0), c.y().min(1.0), c.z().min(1.0)));
+                assert!((reloaded.pixel(i, j) - clamped).length() < 2.0 / 255.0, "pixel ({}, {})", i, j);
+            }
+        }
+    }
+}
+
+#[test]
+fn orders_by_name() {
+    for name in tile::TILE_ORDERS {
+        assert!(TileOrder::by_name(name).is_some());
+    }
+    assert_eq!(TileOrder::by_name("hilbert"), Some(TileOrder::Hilbert));
+    assert_eq!(TileOrder::by_name("zigzag"), None);
+}
@@ -0,0 +1,27 @@
+// Tinted glass needs a positive distance to reach its tint over
+use lib::basic_types::vec3::Color;
+use lib::basic_types::vec3::Vec3Traits;
+
+use lib::gfx::material::dielectric::Dielectric;
+use lib::gfx::material::dielectric::DielectricFn;
+use lib::gfx::material::rough_dielectric::RoughDielectric;
+use lib::gfx::material::rough_dielectric::RoughDielectricFn;
+
+#[test]
+fn accepts_a_positive_distance() {
+    Dielectric::new_tinted(1.5, Color::new((1.0, 0.5, 0.2)), 0.5);
+    RoughDielectric::new_tinted(1.5, 0.2, Color::new((1.0, 0.5, 0.2)), 0.5);
+}
+
+#[test]
+#[should_panic(expected = "tint distance must be positive")]
+fn rejects_a_zero_distance() {
+    // a clear channel over no distance would be 0/0
+    Dielectric::new_tinted(1.5, Color::new((1.0, 0.5, 0.2)), 0.0);
+}
+
+#[test]
+#[should_panic(expected = "tint distance must be positive")]
+fn rejects_a_negative_distance() {
+    RoughDielectric::new_tinted(1.5, 0.2, Color::new((0.8, 0.8, 0.8)), -1.0);
+}